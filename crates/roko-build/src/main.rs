@@ -1,7 +1,9 @@
 use std::{
-    path::PathBuf,
+    fs,
+    path::{Path, PathBuf},
     process::Command,
     sync::{atomic::AtomicBool, mpsc, Arc},
+    time::SystemTime,
 };
 
 use notify::{event::EventKind, Event, RecursiveMode, Watcher};
@@ -13,6 +15,8 @@ fn main() {
     }
 
     let (sender, recv) = mpsc::channel();
+    let target = target_dir();
+    let style_dir = target.join("roko").join("styles");
 
     let arg = std::env::args().nth(1);
 
@@ -46,16 +50,22 @@ fn main() {
             is_building.store(true, std::sync::atomic::Ordering::SeqCst);
             println!("[INFO]: Building Roko application");
             println!("[INFO]: Building Rust");
-            spawn_web_pack(&["--dev", "--target", "web"]);
+            let started = SystemTime::now();
+            spawn_web_pack(&style_dir, &["--dev", "--target", "web"]);
             println!("[INFO]: Building CSS");
+            remove_stale_styles(&style_dir, &target, started);
+            bundle_css(&style_dir);
             println!("[INFO]: Finished building Roko application");
             is_building.store(false, std::sync::atomic::Ordering::SeqCst);
         }
     } else if let Some("build") = arg.as_deref() {
         println!("[INFO]: Building Roko application");
         println!("[INFO]: Building Rust");
-        spawn_web_pack(&[]);
+        let started = SystemTime::now();
+        spawn_web_pack(&style_dir, &[]);
         println!("[INFO]: Building CSS");
+        remove_stale_styles(&style_dir, &target, started);
+        bundle_css(&style_dir);
         println!("[INFO]: Finished building Roko application");
     } else {
         eprintln!("Please choose between 'build' and 'watch'");
@@ -63,10 +73,166 @@ fn main() {
     }
 }
 
-fn spawn_web_pack(extra_args: &[&str]) {
+fn spawn_web_pack(style_dir: &Path, extra_args: &[&str]) {
     let mut args: Vec<&str> = vec!["--log-level", "error", "build", "--out-dir", "static"];
 
     args.extend(extra_args);
 
-    Command::new("wasm-pack").args(args).status().unwrap();
+    Command::new("wasm-pack")
+        .args(args)
+        .env("ROKO_STYLE_DIR", style_dir)
+        .status()
+        .unwrap();
+}
+
+/// Target dir of the workspace, which isn't the one of the app when it's a member of a workspace.
+/// `style!` writes the styles of each crate in `roko/styles` inside of it while building.
+fn target_dir() -> PathBuf {
+    let output = Command::new("cargo")
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .output()
+        .unwrap();
+
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let target = metadata["target_directory"].as_str().unwrap();
+
+    PathBuf::from(target)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Whether the build that started at `started` compiled `krate`, i.e. it wrote one of its
+/// artifacts, like `libroko_html-1f2e3d.rlib`, in the `deps` dir of a profile.
+fn compiled(target: &Path, krate: &str, started: SystemTime) -> bool {
+    let artifact = |name: &str| {
+        [
+            format!("lib{krate}-"),
+            format!("{krate}-"),
+            format!("{krate}."),
+        ]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+    };
+
+    fs::read_dir(target.join("wasm32-unknown-unknown"))
+        .into_iter()
+        .flatten()
+        .filter_map(|profile| fs::read_dir(profile.ok()?.path().join("deps")).ok())
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| artifact(&entry.file_name().to_string_lossy()))
+        .any(|entry| modified(&entry.path()).is_some_and(|time| time >= started))
+}
+
+/// Removes the stylesheets of the crates that the build compiled without writing them, because
+/// their last `style!` is gone. The crates that weren't compiled keep theirs.
+fn remove_stale_styles(style_dir: &Path, target: &Path, started: SystemTime) {
+    let styles = fs::read_dir(style_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()));
+
+    for path in styles {
+        let Some(krate) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+
+        let stale = modified(&path).is_some_and(|time| time < started);
+
+        if stale && compiled(target, krate, started) {
+            if let Err(error) = fs::remove_file(&path) {
+                eprintln!("[ERROR]: Couldn't remove {}: {error}", path.display());
+            }
+        }
+    }
+}
+
+/// Joins the stylesheets of the crates into `static/style.css`. A crate rewrites its stylesheet
+/// whenever it's compiled, and [remove_stale_styles] removes it when it has no styles left, so
+/// they only have the styles of the last build.
+fn bundle_css(style_dir: &Path) {
+    let mut styles = fs::read_dir(style_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "css"))
+        .collect::<Vec<_>>();
+
+    styles.sort();
+
+    let css = styles
+        .iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .collect::<String>();
+
+    if let Err(error) =
+        fs::create_dir_all("static").and_then(|()| fs::write("static/style.css", css))
+    {
+        eprintln!("[ERROR]: Couldn't write static/style.css: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// An empty directory for a test.
+    fn dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("roko-build-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes an empty artifact of the build to the `deps` dir of `target`.
+    fn artifact(target: &Path, name: &str) {
+        let deps = target.join("wasm32-unknown-unknown/release/deps");
+        fs::create_dir_all(&deps).unwrap();
+        fs::write(deps.join(name), "").unwrap();
+    }
+
+    /// Time between two writes, so their modification times are in order.
+    fn tick() {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    #[test]
+    fn finds_the_crates_that_were_compiled() {
+        let target = dir("compiled");
+        artifact(&target, "libroko_dom-1f2e3d.rlib");
+        tick();
+        let started = SystemTime::now();
+        tick();
+        artifact(&target, "libroko_html-1f2e3d.rlib");
+        artifact(&target, "roko_sofia_client.wasm");
+
+        assert!(compiled(&target, "roko_html", started));
+        assert!(compiled(&target, "roko_sofia_client", started));
+        assert!(!compiled(&target, "roko_dom", started));
+        assert!(!compiled(&target, "roko_http", started));
+    }
+
+    #[test]
+    fn removes_only_the_stale_styles_of_compiled_crates() {
+        let (styles, target) = (dir("styles"), dir("target"));
+        fs::write(styles.join("roko_html.css"), ".a{}").unwrap();
+        fs::write(styles.join("roko_dom.css"), ".b{}").unwrap();
+        tick();
+        let started = SystemTime::now();
+        tick();
+        fs::write(styles.join("roko_sofia_client.css"), ".c{}").unwrap();
+        artifact(&target, "libroko_html-1f2e3d.rlib");
+        artifact(&target, "roko_sofia_client.wasm");
+
+        remove_stale_styles(&styles, &target, started);
+
+        assert!(!styles.join("roko_html.css").exists());
+        assert!(styles.join("roko_dom.css").exists());
+        assert!(styles.join("roko_sofia_client.css").exists());
+    }
 }
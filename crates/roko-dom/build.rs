@@ -0,0 +1,100 @@
+//! Generates the element constructors and attribute helpers from the spec of `roko_html`.
+
+use std::fmt::Write;
+
+use roko_html::spec::{self, AttributeSpec};
+use roko_html::Namespace;
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "gen", "macro", "override", "priv", "try",
+    "typeof", "unsized", "virtual", "yield",
+];
+
+/// Rust name of an element or attribute, e.g. `r#type` for `type` and `aria_label` for
+/// `aria-label`.
+fn ident(name: &str) -> String {
+    let ident = name.replace(['-', ':'], "_");
+
+    if KEYWORDS.contains(&ident.as_str()) {
+        format!("r#{ident}")
+    } else {
+        ident
+    }
+}
+
+fn attributes(attributes: &[AttributeSpec]) -> String {
+    let mut out = String::from("attributes! {\n");
+
+    for attribute in attributes {
+        let kind = match (attribute.boolean, attribute.property) {
+            (false, false) => "string",
+            (true, false) => "boolean",
+            (false, true) => "property",
+            (true, true) => "boolean_property",
+        };
+
+        writeln!(
+            out,
+            "    {kind} {} = {:?},",
+            ident(attribute.name),
+            attribute.name
+        )
+        .unwrap();
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", spec::PATH);
+
+    let spec = spec::spec();
+    let out = std::env::var("OUT_DIR").unwrap();
+
+    let mut elements = String::new();
+
+    for (namespace, name) in [
+        (Namespace::Html, "Html"),
+        (Namespace::Svg, "Svg"),
+        (Namespace::MathMl, "MathMl"),
+    ] {
+        writeln!(elements, "elements! {{\n    {name}:").unwrap();
+
+        for element in spec.elements.iter().filter(|e| e.namespace == namespace) {
+            let ident = ident(element.name);
+
+            if ident == element.name {
+                writeln!(elements, "    {ident},").unwrap();
+            } else {
+                writeln!(elements, "    {ident} = {:?},", element.name).unwrap();
+            }
+        }
+
+        elements.push_str("}\n");
+    }
+
+    let mut helpers = attributes(&spec.global);
+    helpers.push_str(&attributes(&spec.aria));
+
+    for (namespace, shared) in &spec.shared {
+        let module = format!("{namespace:?}_shared").to_lowercase();
+        writeln!(helpers, "pub mod {module} {{\n{}}}", attributes(shared)).unwrap();
+    }
+
+    for element in spec.elements.iter().filter(|e| !e.attributes.is_empty()) {
+        writeln!(
+            helpers,
+            "#[allow(non_snake_case)]\npub mod {} {{\n{}}}",
+            ident(element.name),
+            attributes(&element.attributes)
+        )
+        .unwrap();
+    }
+
+    std::fs::write(format!("{out}/elements.rs"), elements).unwrap();
+    std::fs::write(format!("{out}/attributes.rs"), helpers).unwrap();
+}
@@ -0,0 +1,38 @@
+//! Typed helpers for attributes, generated from the spec of `roko_html`. The global and ARIA
+//! attributes are at the top, the ones of an element are in a module with its name and the ones
+//! of every SVG or MathML element in `svg_shared` and `mathml_shared`, e.g.
+//! `attributes::input::r#type("email")` or `attributes::aria_label("Close")`.
+
+macro_rules! attributes {
+    ($($kind:ident $name:ident = $attr:literal),* $(,)?) => {
+        $(attributes!(@$kind $name $attr);)*
+    };
+    (@string $name:ident $attr:literal) => {
+        #[doc = concat!("The `", $attr, "` attribute.")]
+        #[allow(non_snake_case)]
+        pub fn $name<Msg>(value: impl ToString) -> roko_html::Attribute<Msg> {
+            roko_html::Attribute::Custom($attr.to_string(), value.to_string())
+        }
+    };
+    (@boolean $name:ident $attr:literal) => {
+        #[doc = concat!("The `", $attr, "` attribute, which is removed when it's `false`.")]
+        #[allow(non_snake_case)]
+        pub fn $name<Msg>(value: bool) -> roko_html::Attribute<Msg> {
+            roko_html::Attribute::Bool($attr.to_string(), value)
+        }
+    };
+    (@property $name:ident $attr:literal) => {
+        #[doc = concat!("The `", $attr, "` property.")]
+        pub fn $name<Msg>(value: impl ToString) -> roko_html::Attribute<Msg> {
+            roko_html::Attribute::Property($attr.to_string(), roko_html::PropertyValue::from(value.to_string()))
+        }
+    };
+    (@boolean_property $name:ident $attr:literal) => {
+        #[doc = concat!("The `", $attr, "` property.")]
+        pub fn $name<Msg>(value: bool) -> roko_html::Attribute<Msg> {
+            roko_html::Attribute::Property($attr.to_string(), roko_html::PropertyValue::from(value))
+        }
+    };
+}
+
+include!(concat!(env!("OUT_DIR"), "/attributes.rs"));
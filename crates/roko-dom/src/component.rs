@@ -0,0 +1,326 @@
+//! Stateful components. A [Component] owns a local model that lives outside of the application
+//! model, so transient UI state (e.g. whether a dropdown is open) doesn't have to be threaded
+//! through the root `update`. Each instance is identified by its type and its `key`, inside of the
+//! components that mount it, so the same key in two parents gives two instances. The runtime keeps
+//! its model across renders and drops it once a render doesn't mount it anymore. Mounting one
+//! without a `key` is a compile error.
+//!
+//! ```ignore
+//! html! {
+//!     <div>
+//!         <Dropdown::mount key="language" options="en,pt" />
+//!     </div>
+//! }
+//! ```
+
+use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use futures::channel::mpsc::UnboundedSender;
+use roko_html::context::{consume, provider};
+use roko_html::{to_map, Attrs, Children, Html, Key};
+
+/// Type and key of every component from the root to an instance.
+type Path = Vec<(TypeId, String)>;
+
+/// Path of the component whose view contains a node, provided to the components mounted in it.
+/// It's read when the tree is resolved, so components inside of a consumer get it too.
+#[derive(Default)]
+struct Parent(Path);
+
+struct Instance {
+    model: Box<dyn Any>,
+    props: HashMap<String, String>,
+    generation: u64,
+}
+
+thread_local! {
+    static INSTANCES: RefCell<HashMap<Path, Instance>> = RefCell::new(HashMap::new());
+    static GENERATION: Cell<u64> = const { Cell::new(0) };
+    static REDRAW: RefCell<Option<UnboundedSender<()>>> = const { RefCell::new(None) };
+}
+
+/// A piece of UI with its own model and messages. `ParentMsg` is the message type of the view
+/// that instantiates the component, and `update` can return one of them to notify the parent.
+pub trait Component<ParentMsg: 'static>: Sized + 'static {
+    type Model: 'static;
+    type Msg: Clone + PartialEq + 'static;
+
+    /// Creates the model of a new instance from the attributes it was mounted with, see
+    /// [roko_html::to_map].
+    fn init(props: HashMap<String, String>) -> Self::Model;
+
+    /// Called when an instance is mounted again with other attributes. It does nothing by default,
+    /// so the model keeps what `init` made of the first ones.
+    fn changed(_props: HashMap<String, String>, _model: &mut Self::Model) {}
+
+    fn update(msg: Self::Msg, model: &mut Self::Model) -> Option<ParentMsg>;
+
+    fn view(model: &Self::Model) -> Html<Self::Msg>;
+
+    /// Element function used by `html!` to instantiate the component. It takes a [Key], so a
+    /// `key` attribute is required, and the children are ignored, since they can't be embedded in
+    /// a view of another message type.
+    fn mount(key: Key, attrs: Attrs<ParentMsg>, _children: Children<ParentMsg>) -> Html<ParentMsg> {
+        mount::<Self, ParentMsg>(key, attrs)
+    }
+}
+
+/// The instance is found and rendered when the tree is resolved, with the path of the components
+/// around it.
+fn mount<C: Component<P>, P: 'static>(Key(key): Key, attrs: Attrs<P>) -> Html<P> {
+    let props = to_map(attrs);
+
+    consume(move |Parent(parent): &Parent| {
+        let mut path = parent.clone();
+        path.push((TypeId::of::<C>(), key.clone()));
+
+        let view = INSTANCES.with(|instances| {
+            let mut instances = instances.borrow_mut();
+            let instance = instances.entry(path.clone()).or_insert_with(|| Instance {
+                model: Box::new(C::init(props.clone())),
+                props: props.clone(),
+                generation: 0,
+            });
+
+            let model = instance.model.downcast_mut().unwrap();
+            if instance.props != props {
+                instance.props = props.clone();
+                C::changed(props.clone(), model);
+            }
+
+            instance.generation = GENERATION.with(Cell::get);
+            C::view(model)
+        });
+
+        provider(Parent(path.clone()), None, Vec::new(), vec![view])
+            .filter_map_with(path, dispatch::<C, P>)
+    })
+}
+
+/// Runs the update of an instance. Local messages don't reach the application, so a redraw is
+/// requested unless the component produced a message for its parent.
+fn dispatch<C: Component<P>, P: 'static>(path: &Path, msg: C::Msg) -> Option<P> {
+    let parent_msg = INSTANCES.with(|instances| {
+        let mut instances = instances.borrow_mut();
+        let instance = instances.get_mut(path)?;
+        C::update(msg, instance.model.downcast_mut().unwrap())
+    });
+
+    if parent_msg.is_none() {
+        REDRAW.with(|redraw| {
+            if let Some(redraw) = redraw.borrow().as_ref() {
+                redraw.unbounded_send(()).unwrap();
+            }
+        });
+    }
+
+    parent_msg
+}
+
+/// Registers the channel that makes the runtime render again after a local update.
+pub(crate) fn set_redraw(sender: UnboundedSender<()>) {
+    REDRAW.with(|redraw| *redraw.borrow_mut() = Some(sender));
+}
+
+/// Starts a new render. Every instance mounted from now on is marked as alive.
+pub(crate) fn begin_render() {
+    GENERATION.with(|generation| generation.set(generation.get() + 1));
+}
+
+/// Drops the instances that weren't mounted since the last call to [begin_render].
+pub(crate) fn sweep() {
+    let generation = GENERATION.with(Cell::get);
+
+    INSTANCES.with(|instances| {
+        instances
+            .borrow_mut()
+            .retain(|_, instance| instance.generation == generation)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use roko_html::{Attribute, Handler};
+
+    /// Counts its clicks, starting at the `start` attribute, and tells the parent when it reaches
+    /// `limit`.
+    struct Counter;
+
+    #[derive(Clone, PartialEq)]
+    struct Increment;
+
+    impl Component<&'static str> for Counter {
+        type Model = (u32, u32);
+        type Msg = Increment;
+
+        fn init(props: HashMap<String, String>) -> Self::Model {
+            let prop = |name: &str| props.get(name).map_or(0, |value| value.parse().unwrap());
+            (prop("start"), prop("limit"))
+        }
+
+        fn changed(props: HashMap<String, String>, model: &mut Self::Model) {
+            model.1 = props["limit"].parse().unwrap();
+        }
+
+        fn update(_: Increment, (count, limit): &mut Self::Model) -> Option<&'static str> {
+            *count += 1;
+            (count == limit).then_some("limit")
+        }
+
+        fn view((count, _): &Self::Model) -> Html<Increment> {
+            Html::node(
+                "button",
+                None,
+                vec![Attribute::OnClick(Handler::message(Increment))],
+                vec![roko_html::text(count.to_string())],
+            )
+        }
+    }
+
+    fn counter(key: &str, attrs: Vec<(&str, &str)>) -> Html<&'static str> {
+        let attrs = attrs
+            .into_iter()
+            .map(|(name, value)| Attribute::Custom(name.to_string(), value.to_string()))
+            .collect();
+
+        Counter::mount(Key(key.to_string()), attrs, Vec::new())
+    }
+
+    /// Resolves a view like the runtime does on every render.
+    fn render(view: Html<&'static str>) -> Html<&'static str> {
+        begin_render();
+        let html = view.resolve();
+        sweep();
+        html
+    }
+
+    /// Text of the counters of a rendered view.
+    fn counts(html: &Html<&'static str>) -> Vec<String> {
+        match html {
+            Html::Node(node) if node.tag == "button" => match node.children.as_slice() {
+                [Html::Text(text)] => vec![text.clone()],
+                _ => Vec::new(),
+            },
+            Html::Node(node) => node.children.iter().flat_map(counts).collect(),
+            Html::Fragment(children) => children.iter().flat_map(counts).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Clicks the counter at `index` of a rendered view.
+    fn click(html: &Html<&'static str>, index: usize) -> Option<&'static str> {
+        fn buttons<'a>(html: &'a Html<&'static str>, out: &mut Vec<&'a Handler<&'static str>>) {
+            match html {
+                Html::Node(node) => {
+                    for attribute in &node.attributes {
+                        if let Attribute::OnClick(handler) = attribute {
+                            out.push(handler);
+                        }
+                    }
+                    node.children.iter().for_each(|child| buttons(child, out));
+                }
+                Html::Fragment(children) => children.iter().for_each(|child| buttons(child, out)),
+                _ => {}
+            }
+        }
+
+        let mut handlers = Vec::new();
+        buttons(html, &mut handlers);
+        handlers[index].fire().map(|msg| *msg)
+    }
+
+    fn div(children: Vec<Html<&'static str>>) -> Html<&'static str> {
+        Html::node("div", None, Vec::new(), children)
+    }
+
+    #[test]
+    fn keeps_the_model_across_renders() {
+        let view = || counter("a", vec![("start", "5")]);
+
+        let html = render(view());
+        assert_eq!(counts(&html), ["5"]);
+
+        click(&html, 0);
+        click(&html, 0);
+        assert_eq!(counts(&render(view())), ["7"]);
+    }
+
+    #[test]
+    fn sends_messages_to_the_parent() {
+        let html = render(counter("a", vec![("limit", "2")]));
+
+        assert_eq!(click(&html, 0), None);
+        assert_eq!(click(&html, 0), Some("limit"));
+    }
+
+    #[test]
+    fn scopes_instances_by_key() {
+        let view = || div(vec![counter("a", Vec::new()), counter("b", Vec::new())]);
+
+        click(&render(view()), 1);
+        assert_eq!(counts(&render(view())), ["0", "1"]);
+    }
+
+    #[test]
+    fn scopes_instances_by_parent() {
+        /// Mounts a counter with the same key in its view.
+        struct Parent;
+
+        impl Component<&'static str> for Parent {
+            type Model = ();
+            type Msg = &'static str;
+
+            fn init(_: HashMap<String, String>) {}
+
+            fn update(msg: &'static str, _: &mut ()) -> Option<&'static str> {
+                Some(msg)
+            }
+
+            fn view(_: &()) -> Html<&'static str> {
+                counter("a", Vec::new())
+            }
+        }
+
+        let parent = |key: &str| Parent::mount(Key(key.to_string()), Vec::new(), Vec::new());
+        let view = || div(vec![counter("a", Vec::new()), parent("x"), parent("y")]);
+
+        click(&render(view()), 2);
+        assert_eq!(counts(&render(view())), ["0", "0", "1"]);
+    }
+
+    #[test]
+    fn scopes_instances_inside_of_consumers() {
+        let view = |key: &str| {
+            let counter = consume(|_: &()| counter("a", Vec::new()));
+            provider(
+                Parent(vec![(TypeId::of::<Counter>(), key.to_string())]),
+                None,
+                Vec::new(),
+                vec![counter],
+            )
+        };
+
+        click(&render(div(vec![view("x"), view("y")])), 0);
+        assert_eq!(counts(&render(div(vec![view("x"), view("y")]))), ["1", "0"]);
+    }
+
+    #[test]
+    fn drops_instances_that_are_not_mounted() {
+        click(&render(counter("a", Vec::new())), 0);
+
+        render(div(Vec::new()));
+        assert_eq!(counts(&render(counter("a", Vec::new()))), ["0"]);
+    }
+
+    #[test]
+    fn passes_changed_props() {
+        render(counter("a", vec![("limit", "1")]));
+        let html = render(counter("a", vec![("limit", "2")]));
+
+        assert_eq!(click(&html, 0), None);
+        assert_eq!(click(&html, 0), Some("limit"));
+    }
+}
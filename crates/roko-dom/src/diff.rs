@@ -1,5 +1,8 @@
 //! Diffing algorithm for HTML nodes. It simply compares two nodes and returns a [Patch] that can
-//! be applied to the DOM to update it.
+//! be applied to the DOM to update it. Children are compared in order, unless some of them have a
+//! `key`: then they are matched by it, so a moved element keeps its DOM node and its state.
+
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use roko_html::{Attribute, Html};
 
@@ -12,12 +15,112 @@ pub trait Diff {
     fn diff(old: Self, new: Self) -> Self::Output;
 }
 
+/// Places the children of fragments directly in the list, in the same way they are rendered.
+fn flatten<Msg>(children: Vec<Html<Msg>>) -> Vec<Html<Msg>> {
+    let mut flat = Vec::with_capacity(children.len());
+
+    for child in children {
+        match child {
+            Html::Fragment(children) => flat.extend(flatten(children)),
+            child => flat.push(child),
+        }
+    }
+
+    flat
+}
+
+/// Key of an element, given by the `key` attribute.
+fn key<Msg>(html: &Html<Msg>) -> Option<&str> {
+    match html {
+        Html::Node(node) => node.id.as_deref(),
+        _ => None,
+    }
+}
+
+/// Whether the node of `old` can be patched into `new` instead of being replaced.
+fn same_kind<Msg>(old: &Html<Msg>, new: &Html<Msg>) -> bool {
+    match (old, new) {
+        (Html::Node(old), Html::Node(new)) => old.tag == new.tag && old.namespace == new.namespace,
+        (Html::Text(_), Html::Text(_)) | (Html::UnsafeRawHtml(_), Html::UnsafeRawHtml(_)) => true,
+        _ => false,
+    }
+}
+
+/// Old child matched by every new one, keyed children by their key and the other ones in order.
+/// Returns `None` when every child stays in place, so the children can be patched in order.
+fn matches<Msg>(old: &[Html<Msg>], new: &[Html<Msg>]) -> Option<Vec<Option<usize>>> {
+    let mut by_key = HashMap::new();
+    let mut unkeyed = VecDeque::new();
+
+    for (at, child) in old.iter().enumerate() {
+        match key(child) {
+            Some(key) => {
+                by_key.insert(key, at);
+            }
+            None => unkeyed.push_back(at),
+        }
+    }
+
+    let matches: Vec<_> = new
+        .iter()
+        .map(|child| {
+            let from = match key(child) {
+                Some(key) => by_key.remove(key),
+                None => unkeyed.pop_front(),
+            };
+            from.filter(|&from| same_kind(&old[from], child))
+        })
+        .collect();
+
+    let in_place = old.len() == new.len() && (0..old.len()).all(|at| matches[at] == Some(at));
+    (!in_place).then_some(matches)
+}
+
+/// Every new child is either a [Patch::Move] of the old one it matches or a [Patch::Insert], and
+/// the old children that aren't matched are removed after them.
+fn diff_keyed<Msg: PartialEq + Eq>(
+    old: Vec<Html<Msg>>,
+    new: Vec<Html<Msg>>,
+    matches: Vec<Option<usize>>,
+) -> Vec<Patch<Msg>> {
+    let mut old: Vec<_> = old.into_iter().map(Some).collect();
+    let mut patches = Vec::with_capacity(new.len());
+
+    for (child, from) in new.into_iter().zip(matches) {
+        match from.and_then(|from| Some((from, old[from].take()?))) {
+            Some((from, old)) => patches.push(Patch::Move(from, Box::new(Diff::diff(old, child)))),
+            None => patches.push(Patch::Insert(child)),
+        }
+    }
+
+    for (from, child) in old.into_iter().enumerate() {
+        let key = match child {
+            Some(Html::Node(node)) => node.id,
+            Some(_) => None,
+            None => continue,
+        };
+        patches.push(Patch::Move(from, Box::new(Patch::Remove(key))));
+    }
+
+    patches
+}
+
 impl<Msg: PartialEq + Eq> Diff for Vec<Html<Msg>> {
     type Output = Vec<Patch<Msg>>;
 
     fn diff(old: Vec<Html<Msg>>, new: Vec<Html<Msg>>) -> Vec<Patch<Msg>> {
         let mut patches = Vec::new();
 
+        let old = flatten(old);
+        let new = flatten(new);
+
+        // Keyed children that stay in place are patched in order like the other ones.
+        if old.iter().chain(&new).any(|child| key(child).is_some()) {
+            if let Some(matches) = matches(&old, &new) {
+                return diff_keyed(old, new, matches);
+            }
+        }
+
         let min_len = old.len().min(new.len());
 
         let mut added_children = new.into_iter();
@@ -34,7 +137,7 @@ impl<Msg: PartialEq + Eq> Diff for Vec<Html<Msg>> {
         patches.extend(removed_children.map(|s| {
             Patch::Remove(match s {
                 Html::Node(s) => s.id,
-                Html::Text(_) => None,
+                _ => None,
             })
         }));
 
@@ -42,30 +145,110 @@ impl<Msg: PartialEq + Eq> Diff for Vec<Html<Msg>> {
     }
 }
 
+/// Patches that turn the attribute `old` into `new`, which set the same thing.
+fn diff_attribute<Msg: PartialEq + Eq>(
+    old: Attribute<Msg>,
+    new: Attribute<Msg>,
+    patches: &mut Vec<AttrPatch<Msg>>,
+) {
+    match (&old, &new) {
+        // Properties are compared with the live value when the patch is applied, since
+        // the user may have changed them since the last view.
+        (Attribute::Property(old_name, _), Attribute::Property(new_name, _))
+            if old_name == new_name =>
+        {
+            patches.push(AttrPatch::Add(new))
+        }
+        // A new handler replaces the old one behind the listener of the element.
+        (Attribute::OnClick(_), Attribute::OnClick(_))
+        | (Attribute::OnInput(_), Attribute::OnInput(_))
+        | (Attribute::OnChange(_), Attribute::OnChange(_))
+            if old != new =>
+        {
+            patches.push(AttrPatch::Add(new))
+        }
+        (Attribute::Class(old_classes), Attribute::Class(new_classes)) => {
+            let add: Vec<_> = new_classes
+                .iter()
+                .filter(|class| !old_classes.contains(class))
+                .cloned()
+                .collect();
+            let remove: Vec<_> = old_classes
+                .iter()
+                .filter(|class| !new_classes.contains(class))
+                .cloned()
+                .collect();
+
+            if !add.is_empty() || !remove.is_empty() {
+                patches.push(AttrPatch::Classes { add, remove });
+            }
+        }
+        (Attribute::Style(old_style), Attribute::Style(new_style)) => {
+            // Later declarations win, like in the attribute.
+            let value = |style: &[(String, String)], name: &str| {
+                style
+                    .iter()
+                    .rev()
+                    .find(|(other, _)| other == name)
+                    .map(|(_, value)| value.clone())
+            };
+
+            let set: Vec<_> = new_style
+                .iter()
+                .filter(|(name, new_value)| value(old_style, name).as_ref() != Some(new_value))
+                .filter(|(name, new_value)| value(new_style, name).as_ref() == Some(new_value))
+                .cloned()
+                .collect();
+            let remove: Vec<_> = old_style
+                .iter()
+                .filter(|(name, _)| value(new_style, name).is_none())
+                .map(|(name, _)| name.clone())
+                .collect();
+
+            if !set.is_empty() || !remove.is_empty() {
+                patches.push(AttrPatch::Styles { set, remove });
+            }
+        }
+        _ if old != new => {
+            patches.push(AttrPatch::Remove(old));
+            patches.push(AttrPatch::Add(new));
+        }
+        _ => (),
+    }
+}
+
 impl<Msg: PartialEq + Eq> Diff for Vec<Attribute<Msg>> {
     type Output = Vec<AttrPatch<Msg>>;
 
+    /// Attributes are matched by their [Attribute::key], since spreads can change how many there
+    /// are and where they are between renders.
     fn diff(old: Vec<Attribute<Msg>>, new: Vec<Attribute<Msg>>) -> Vec<AttrPatch<Msg>> {
-        let mut patches = Vec::new();
-
-        let min_len = old.len().min(new.len());
-
-        let mut added_attrs = new.into_iter();
-        let mut removed_attrs = old.into_iter();
+        let keys: HashSet<_> = new.iter().map(Attribute::key).collect();
+        let mut old: Vec<_> = old.into_iter().map(Some).collect();
+        let mut changes = Vec::new();
 
-        let new_iter: Vec<_> = added_attrs.by_ref().take(min_len).collect();
-        let old_iter: Vec<_> = removed_attrs.by_ref().take(min_len).collect();
+        for new in new {
+            let key = new.key();
+            let matched = old
+                .iter_mut()
+                .find(|old| old.as_ref().is_some_and(|old| old.key() == key))
+                .and_then(Option::take);
 
-        for (old, new) in old_iter.into_iter().zip(new_iter.into_iter()) {
-            if old != new {
-                patches.push(AttrPatch::Remove(old));
-                patches.push(AttrPatch::Add(new));
+            match matched {
+                Some(old) => diff_attribute(old, new, &mut changes),
+                None => changes.push(AttrPatch::Add(new)),
             }
         }
 
-        patches.extend(added_attrs.map(AttrPatch::Add));
-        patches.extend(removed_attrs.map(AttrPatch::Remove));
+        // Removals go first and skip what the new attributes set, so they don't undo it.
+        let mut patches: Vec<_> = old
+            .into_iter()
+            .flatten()
+            .filter(|old| !keys.contains(&old.key()))
+            .map(AttrPatch::Remove)
+            .collect();
 
+        patches.extend(changes);
         patches
     }
 }
@@ -75,11 +258,28 @@ impl<Msg: PartialEq + Eq> Diff for Html<Msg> {
 
     fn diff(old: Html<Msg>, new: Html<Msg>) -> Patch<Msg> {
         match (old, new) {
+            // Contexts and maps are resolved and fragments flattened into their parent before
+            // diffing, if one is left the node is rendered again.
+            (Html::Provider(_) | Html::Consumer(_) | Html::Map(_) | Html::Fragment(_), new)
+            | (
+                _,
+                new @ (Html::Provider(_) | Html::Consumer(_) | Html::Map(_) | Html::Fragment(_)),
+            ) => {
+                debug_assert!(false, "the tree wasn't resolved before diffing");
+                Patch::Replace(new)
+            }
             (Html::Node(_), Html::Text(t)) => Patch::Replace(Html::Text(t)),
             (Html::Text(_), Html::Node(t)) => Patch::Replace(Html::Node(t)),
             (Html::Text(old), Html::Text(new_text)) if old == *new_text => Patch::Nothing,
-            (Html::Text(_), Html::Text(t)) => Patch::Replace(Html::Text(t)),
-            (Html::Node(old), Html::Node(new_ui)) if old.tag != new_ui.tag => {
+            (Html::Text(_), Html::Text(t)) => Patch::Text(t),
+            (Html::UnsafeRawHtml(old), Html::UnsafeRawHtml(new)) if old == new => Patch::Nothing,
+            (Html::UnsafeRawHtml(_), Html::UnsafeRawHtml(new)) => Patch::RawHtml(new),
+            (Html::UnsafeRawHtml(_), new) | (_, new @ Html::UnsafeRawHtml(_)) => {
+                Patch::Replace(new)
+            }
+            (Html::Node(old), Html::Node(new_ui))
+                if old.tag != new_ui.tag || old.namespace != new_ui.namespace =>
+            {
                 Patch::Replace(Html::Node(new_ui))
             }
             (Html::Node(old_ui), Html::Node(new_ui)) => {
@@ -97,3 +297,265 @@ impl<Msg: PartialEq + Eq> Diff for Html<Msg> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use roko_html::Namespace;
+
+    fn li(key: Option<&str>, text: &str) -> Html<()> {
+        Html::node(
+            "li",
+            key.map(str::to_string),
+            Vec::new(),
+            vec![roko_html::text(text)],
+        )
+    }
+
+    fn diff(old: Vec<Html<()>>, new: Vec<Html<()>>) -> String {
+        format!("{:?}", Diff::diff(old, new))
+    }
+
+    #[test]
+    fn patches_unkeyed_children_in_order() {
+        let old = vec![li(None, "a"), li(None, "b")];
+        let new = vec![li(None, "b"), li(None, "a")];
+
+        assert!(diff(old, new).starts_with("[Update([Text(\"b\")], []), Update("));
+    }
+
+    #[test]
+    fn moves_keyed_children() {
+        let old = vec![li(Some("a"), "a"), li(Some("b"), "b"), li(Some("c"), "c")];
+        let new = vec![li(Some("c"), "c"), li(Some("a"), "a!")];
+
+        assert_eq!(
+            diff(old, new),
+            "[Move(2, Nothing), Move(0, Update([Text(\"a!\")], [])), Move(1, Remove(Some(\"b\")))]"
+        );
+    }
+
+    #[test]
+    fn inserts_new_keyed_children() {
+        let old = vec![li(Some("a"), "a")];
+        let new = vec![li(Some("b"), "b"), li(Some("a"), "a")];
+
+        let patches = diff(old, new);
+        assert!(patches.starts_with("[Insert(Node"), "{patches}");
+        assert!(patches.ends_with("Move(0, Nothing)]"), "{patches}");
+    }
+
+    #[test]
+    fn keeps_keyed_children_in_place() {
+        let old = vec![li(Some("a"), "a"), li(Some("b"), "b")];
+        let new = vec![li(Some("a"), "a"), li(Some("b"), "b!")];
+
+        assert_eq!(diff(old, new), "[Nothing, Update([Text(\"b!\")], [])]");
+    }
+
+    #[test]
+    fn diffs_the_children_of_fragments_in_place() {
+        let old = vec![li(None, "a"), Html::Fragment(vec![li(None, "b")])];
+        let new = vec![
+            Html::Fragment(vec![li(None, "a"), li(None, "b!")]),
+            li(None, "c"),
+        ];
+
+        let patches = diff(old, new);
+        assert!(
+            patches.starts_with("[Nothing, Update([Text(\"b!\")], []), Add(Node"),
+            "{patches}"
+        );
+    }
+
+    #[test]
+    fn replaces_nodes_of_another_namespace() {
+        let node = |namespace| Html::<()>::node_ns(namespace, "a", None, Vec::new(), Vec::new());
+
+        assert!(matches!(
+            Diff::diff(node(Namespace::Html), node(Namespace::Svg)),
+            Patch::Replace(Html::Node(roko_html::Node {
+                namespace: Namespace::Svg,
+                ..
+            }))
+        ));
+        assert!(matches!(
+            Diff::diff(node(Namespace::Svg), node(Namespace::Svg)),
+            Patch::Nothing
+        ));
+    }
+
+    #[test]
+    fn patches_raw_html_when_its_markup_changes() {
+        let raw = |html| vec![roko_html::unsafe_raw_html::<()>(html)];
+
+        assert_eq!(diff(raw("<b>a</b>"), raw("<b>a</b>")), "[Nothing]");
+        assert_eq!(
+            diff(raw("<b>a</b>"), raw("<i>b</i>")),
+            "[RawHtml(\"<i>b</i>\")]"
+        );
+        assert_eq!(
+            diff(raw("<b>a</b>"), vec![roko_html::text("a")]),
+            "[Replace(Text(\"a\"))]"
+        );
+        assert!(diff(vec![li(None, "a")], raw("<li>a</li>"))
+            .starts_with("[Replace(UnsafeRawHtml(\"<li>a</li>\"))"));
+    }
+
+    fn attrs(old: Vec<Attribute<()>>, new: Vec<Attribute<()>>) -> String {
+        format!("{:?}", Diff::diff(old, new))
+    }
+
+    fn custom(name: &str, value: &str) -> Attribute<()> {
+        Attribute::Custom(name.to_string(), value.to_string())
+    }
+
+    fn class(name: &str) -> Attribute<()> {
+        Attribute::Class(vec![name.to_string()])
+    }
+
+    #[test]
+    fn keeps_attributes_when_a_spread_shrinks() {
+        let old = vec![custom("aria-label", "Close"), class("x")];
+        let new = vec![class("x")];
+
+        assert_eq!(
+            attrs(old, new),
+            "[Remove(Custom(\"aria-label\", \"Close\"))]"
+        );
+    }
+
+    #[test]
+    fn matches_reordered_attributes() {
+        let old = vec![custom("title", "a"), class("x"), custom("id", "b")];
+        let new = vec![custom("id", "b"), custom("title", "c"), class("x")];
+
+        assert_eq!(
+            attrs(old, new),
+            "[Remove(Custom(\"title\", \"a\")), Add(Custom(\"title\", \"c\"))]"
+        );
+    }
+
+    #[test]
+    fn adds_attributes_when_a_spread_grows() {
+        let old = vec![class("x")];
+        let new = vec![custom("role", "button"), class("y")];
+
+        assert_eq!(
+            attrs(old, new),
+            "[Add(Custom(\"role\", \"button\")), Classes { add: [\"y\"], remove: [\"x\"] }]"
+        );
+    }
+
+    #[test]
+    fn replaces_a_class_attribute_with_a_class_list() {
+        let old = vec![custom("class", "x"), custom("class", "y")];
+        let new = vec![class("z")];
+
+        assert_eq!(
+            attrs(old, new),
+            "[Remove(Custom(\"class\", \"x\")), Add(Class([\"z\"]))]"
+        );
+    }
+
+    fn style(declarations: &[(&str, &str)]) -> Attribute<()> {
+        Attribute::Style(
+            declarations
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn patches_only_the_classes_that_changed() {
+        let old = vec![Attribute::Class(vec![
+            "bar".to_string(),
+            "active".to_string(),
+        ])];
+        let new = vec![Attribute::Class(vec![
+            "bar".to_string(),
+            "done".to_string(),
+        ])];
+
+        assert_eq!(
+            attrs(old, new),
+            "[Classes { add: [\"done\"], remove: [\"active\"] }]"
+        );
+        assert_eq!(attrs(vec![class("x")], vec![class("x")]), "[]");
+    }
+
+    #[test]
+    fn patches_only_the_style_properties_that_changed() {
+        let old = vec![style(&[
+            ("height", "4px"),
+            ("width", "10%"),
+            ("color", "red"),
+        ])];
+        let new = vec![style(&[
+            ("height", "4px"),
+            ("width", "20%"),
+            ("width", "40%"),
+        ])];
+
+        assert_eq!(
+            attrs(old, new),
+            "[Styles { set: [(\"width\", \"40%\")], remove: [\"color\"] }]"
+        );
+    }
+
+    #[test]
+    fn toggles_boolean_attributes() {
+        let disabled = |value| vec![Attribute::Bool("disabled".to_string(), value)];
+
+        assert_eq!(attrs(disabled(true), disabled(true)), "[]");
+        assert_eq!(
+            attrs(disabled(true), disabled(false)),
+            "[Remove(Bool(\"disabled\", true)), Add(Bool(\"disabled\", false))]"
+        );
+        assert_eq!(
+            attrs(disabled(false), vec![]),
+            "[Remove(Bool(\"disabled\", false))]"
+        );
+    }
+
+    /// A button sending `msg` inside of a map of a view of numbers.
+    fn mapped(msg: i32, map: impl Fn(Html<i32>) -> Html<String>) -> Html<String> {
+        let button = Html::node(
+            "button",
+            None,
+            vec![Attribute::OnClick(roko_html::Handler::message(msg))],
+            vec![roko_html::text("go")],
+        );
+
+        map(button.map(|n| n + 1)).resolve()
+    }
+
+    #[test]
+    fn keeps_mapped_handlers() {
+        let view = |msg| mapped(msg, |html| html.map(|n| n.to_string()));
+
+        assert_eq!(format!("{:?}", Html::diff(view(1), view(1))), "Nothing");
+        assert!(format!("{:?}", Html::diff(view(1), view(2))).contains("OnClick"));
+    }
+
+    #[test]
+    fn patches_handlers_of_maps_that_capture() {
+        let view =
+            |suffix: &'static str| mapped(1, move |html| html.map(move |n| format!("{n}{suffix}")));
+
+        assert!(format!("{:?}", Html::diff(view("!"), view("!"))).contains("OnClick"));
+    }
+
+    #[test]
+    fn compares_the_state_of_maps() {
+        let view = |suffix: &'static str| {
+            mapped(1, |html| {
+                html.filter_map_with(suffix, |suffix, n| Some(format!("{n}{suffix}")))
+            })
+        };
+
+        assert_eq!(format!("{:?}", Html::diff(view("!"), view("!"))), "Nothing");
+        assert!(format!("{:?}", Html::diff(view("!"), view("?"))).contains("OnClick"));
+    }
+}
@@ -1,28 +1,27 @@
 /// This module contains functions for creating HTML elements. These functions are used by the
-/// `roko_macro` crate to create the HTML elements that are rendered to the DOM.
-use roko_html::{Attrs, Children, Html};
+/// `roko_macro` crate to create the HTML elements that are rendered to the DOM. They are generated
+/// from the spec of `roko_html`, together with the helpers of [crate::attributes].
+use roko_html::{Attrs, Children, Html, Namespace};
 
+/// Elements of a namespace. Tags that are keywords, like `use`, are given with their name.
 macro_rules! elements {
-    ($($name:ident),+) => {
+    ($namespace:ident: $($name:ident $(= $tag:literal)?),* $(,)?) => {
         $(
             #[inline(always)]
+            #[allow(non_snake_case)]
             pub fn $name<Msg>(
                 id: Option<String>,
                 attrs: Attrs<Msg>,
                 children: Children<Msg>,
             ) -> Html<Msg> {
-                Html::node(stringify!($name), id, attrs, children)
+                Html::node_ns(Namespace::$namespace, elements!(@tag $name $($tag)?), id, attrs, children)
             }
-        )+
+        )*
     };
-    () => {};
+    (@tag $name:ident $tag:literal) => { $tag };
+    (@tag $name:ident) => { stringify!($name) };
 }
 
-elements! {
-    p, div, span, main, a, ul, li, img, h1, h2, h3, h4, h5, h6, button, input, form,
-    textarea, label, pre, code, select, option, table, thead, tbody, tr, td, th, i, b, strong,
-    em, small, hr, nav, footer, article, aside, details, summary, fieldset, legend,
-    audio, video, canvas, figure, figcaption, time, datalist, keygen, output, progress, meter,
-    rt, rp, samp, sub, sup, mark, wbr, bdi, bdo, cite, q, dfn, abbr, address, del, ins, menu,
-    header, section
-}
+// Tags like `a` or `title` are shared by HTML and SVG, inside of an `<svg>` they are created in the
+// SVG namespace when rendered.
+include!(concat!(env!("OUT_DIR"), "/elements.rs"));
@@ -1,15 +1,116 @@
 //! Module to data structures and functions related to events in the browser. This module contains
 //! functions to handle events in the browser.
 
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
 use std::sync::Arc;
 
 use crate::Channel;
 
 use wasm_bindgen::prelude::Closure;
-use wasm_bindgen::JsCast;
+use wasm_bindgen::{JsCast, JsValue};
 
 use web_sys::HashChangeEvent;
 
+/// What an element does when one of its events fires.
+pub(crate) type EventHandler = Rc<dyn Fn(&web_sys::Event)>;
+
+type Slot = Rc<RefCell<Option<EventHandler>>>;
+
+/// Handlers of an element, one for each event that it listens to.
+struct Handlers {
+    element: web_sys::Element,
+    slots: HashMap<&'static str, Slot>,
+}
+
+/// Property of an element with its id in [HANDLERS].
+const HANDLERS_ID: &str = "__rokoHandlers";
+
+thread_local! {
+    static HANDLERS: RefCell<HashMap<u32, Handlers>> = RefCell::new(HashMap::new());
+    static NEXT_ID: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Sets what the element does when `event` fires, `None` stops handling it. The first handler of
+/// an event adds a listener that calls whatever handler is set when it fires, so views that build
+/// a new handler on every render only swap the Rust side and never add another JS closure.
+pub(crate) fn set_handler(
+    element: &web_sys::Element,
+    event: &'static str,
+    handler: Option<EventHandler>,
+) {
+    let key = JsValue::from_str(HANDLERS_ID);
+
+    let id = match js_sys::Reflect::get(element, &key)
+        .ok()
+        .and_then(|id| id.as_f64())
+    {
+        Some(id) => id as u32,
+        None => {
+            let id = NEXT_ID.with(|next| next.replace(next.get() + 1));
+            js_sys::Reflect::set(element, &key, &JsValue::from(id)).unwrap();
+            id
+        }
+    };
+
+    HANDLERS.with(|handlers| {
+        let mut handlers = handlers.borrow_mut();
+        let handlers = handlers.entry(id).or_insert_with(|| Handlers {
+            element: element.clone(),
+            slots: HashMap::new(),
+        });
+
+        if let Some(slot) = handlers.slots.get(event) {
+            *slot.borrow_mut() = handler;
+            return;
+        }
+
+        let Some(handler) = handler else {
+            return;
+        };
+
+        let slot = Rc::new(RefCell::new(Some(handler)));
+        let current = slot.clone();
+
+        let closure = Closure::<dyn FnMut(web_sys::Event)>::new(move |event: web_sys::Event| {
+            // The handler is cloned out of the slot, so it can replace itself.
+            let handler = current.borrow().clone();
+            if let Some(handler) = handler {
+                handler(&event);
+            }
+        });
+
+        element
+            .add_event_listener_with_callback(event, closure.as_ref().unchecked_ref())
+            .unwrap();
+
+        // The listener keeps the closure alive, and it's freed with the element where the browser
+        // supports weak references.
+        let _ = closure.into_js_value();
+
+        handlers.slots.insert(event, slot);
+    });
+}
+
+/// Forgets the handlers of the elements that were removed from the page, called after a patch.
+pub(crate) fn sweep_handlers() {
+    HANDLERS.with(|handlers| {
+        handlers.borrow_mut().retain(|_, handlers| {
+            let connected = handlers.element.is_connected();
+
+            // The listener outlives the registry, so it's left with an empty slot.
+            if !connected {
+                for slot in handlers.slots.values() {
+                    slot.borrow_mut().take();
+                }
+            }
+
+            connected
+        })
+    });
+}
+
 pub fn window() -> web_sys::Window {
     web_sys::window().unwrap()
 }
@@ -1,9 +1,12 @@
 //! This module renders a virtual dom to a the real dom, does patching and reconciliation.
 
+pub mod attributes;
+pub mod component;
 pub mod diff;
 pub mod elements;
 pub mod events;
 pub mod patch;
+pub mod ports;
 pub mod render;
 
 use std::pin::Pin;
@@ -18,7 +21,7 @@ use crate::diff::Diff;
 use crate::render::Render;
 
 use futures::channel::mpsc::{self, UnboundedSender};
-use futures::{Future, StreamExt};
+use futures::{Future, FutureExt, StreamExt};
 use roko_html::Html;
 
 pub type Channel<Msg> = UnboundedSender<Arc<Msg>>;
@@ -56,6 +59,19 @@ impl<Model, Msg: 'static> Cmd<Model, Msg> {
             model,
         }
     }
+
+    /// Lifts the command of a nested component into the parent, wrapping its model with `model`
+    /// and the message it produces with `msg`. It's the counterpart of [roko_html::Html::map].
+    pub fn map<NewModel, NewMsg>(
+        self,
+        model: impl FnOnce(Model) -> NewModel,
+        msg: impl Fn(Msg) -> NewMsg + 'static,
+    ) -> Cmd<NewModel, NewMsg> {
+        Cmd {
+            future: Box::pin(self.future.map(move |res| res.map(msg))),
+            model: model(self.model),
+        }
+    }
 }
 
 impl<T: Unpin> Future for Cmd<T, ()> {
@@ -89,7 +105,7 @@ pub async fn start<
 >(
     mut view: V,
     mut update: U,
-    mut init: Cmd<Model, Msg>,
+    init: Cmd<Model, Msg>,
     subscriptions: fn(UnboundedSender<Arc<Msg>>) -> (),
     on_mount: Option<Box<dyn Fn(dom::Element, String)>>,
     on_unmount: Option<Box<dyn Fn(dom::Element, String)>>,
@@ -102,53 +118,82 @@ where
     let document = window.document().expect("should have a document on window");
     let body = document.body().expect("document should have a body");
 
-    // The initial view of the application.
+    // The initial view of the application. The root is kept as a list, so a view can return a
+    // fragment and every root node can be replaced. Nodes that were in the body before the
+    // application started are left alone.
     // TODO: Hydration
-    let mut result: Html<Msg> = view(&init.model);
+    component::begin_render();
+    let mut result: Vec<Html<Msg>> = vec![view(&init.model).resolve()];
+    component::sweep();
 
-    let (sender, mut recv) = mpsc::unbounded();
+    let (sender, recv) = mpsc::unbounded();
+    let (redraw, redraw_recv) = mpsc::unbounded();
+
+    component::set_redraw(redraw);
 
     let sender_to = sender.clone();
 
     subscriptions(sender.clone());
 
-    let res = result.render(
-        body.clone().into(),
-        &mut Context {
-            channel: sender_to.clone(),
-            on_mount: &on_mount,
-            on_unmount: &on_unmount,
-        },
-    );
+    let offset = body.child_nodes().length();
 
-    if let Some(el) = res {
-        body.append_child(&el.clone()).unwrap();
+    for html in &result {
+        let res = html.render(
+            body.clone().into(),
+            &mut Context {
+                channel: sender_to.clone(),
+                on_mount: &on_mount,
+                on_unmount: &on_unmount,
+            },
+        );
 
-        if let Some(msg) = init.future.await {
-            sender.unbounded_send(Arc::new(msg)).unwrap();
+        if let Some(node) = res {
+            body.append_child(&node).unwrap();
         }
+    }
 
-        while let Some(msg) = recv.next().await {
-            let new_init = update(msg.as_ref().clone(), init.model);
+    let Cmd { future, mut model } = init;
+
+    if let Some(msg) = future.await {
+        sender.unbounded_send(Arc::new(msg)).unwrap();
+    }
+
+    // Local updates of stateful components only need a new render of the current model.
+    let mut events =
+        futures::stream::select(recv.map(Some), redraw_recv.map(|()| None::<Arc<Msg>>));
+
+    while let Some(msg) = events.next().await {
+        let future = if let Some(msg) = msg {
+            let cmd = update(msg.as_ref().clone(), model);
+            model = cmd.model;
+            Some(cmd.future)
+        } else {
+            None
+        };
 
-            init = new_init;
+        component::begin_render();
+        let result_new = vec![view(&model).resolve()];
+        component::sweep();
 
-            let result_new = view(&init.model);
+        let diff = Diff::diff(result, result_new.clone());
 
-            let diff = Diff::diff(result, result_new.clone());
+        result = result_new;
 
-            result = result_new;
+        patch::apply_children(
+            &body,
+            offset,
+            diff,
+            &mut Context {
+                channel: sender_to.clone(),
+                on_mount: &on_mount,
+                on_unmount: &on_unmount,
+            },
+        );
 
-            diff.apply(
-                el.clone(),
-                &mut Context {
-                    channel: sender_to.clone(),
-                    on_mount: &on_mount,
-                    on_unmount: &on_unmount,
-                },
-            );
+        events::sweep_handlers();
 
-            if let Some(msg) = init.future.await {
+        if let Some(future) = future {
+            if let Some(msg) = future.await {
                 sender.unbounded_send(Arc::new(msg)).unwrap();
             }
         }
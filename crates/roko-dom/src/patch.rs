@@ -1,21 +1,32 @@
 //! Module for patching the DOM with the [Patch] type that express the difference between the last
 //! evaluation of the virtual dom and the current one.
 
-use roko_html::{Attribute, Html};
+use roko_html::{Attribute, Html, PropertyValue};
 
-use dom::{HtmlCollection, HtmlElement};
+use dom::HtmlElement;
 use futures::SinkExt;
 use std::fmt::Debug;
 
 use wasm_bindgen::JsCast;
 use web_sys as dom;
 
-use crate::render::{Context, Render};
+use crate::events::set_handler;
+use crate::render::{property_value, set_property, Context, Render};
 
 /// Patch for attributes
 pub enum AttrPatch<Msg> {
     Add(Attribute<Msg>),
     Remove(Attribute<Msg>),
+    /// Changes only the classes that were added or removed from an [Attribute::Class].
+    Classes {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Changes only the style properties that were set or removed from an [Attribute::Style].
+    Styles {
+        set: Vec<(String, String)>,
+        remove: Vec<String>,
+    },
 }
 
 impl<Msg> Debug for AttrPatch<Msg> {
@@ -23,6 +34,16 @@ impl<Msg> Debug for AttrPatch<Msg> {
         match self {
             Self::Add(arg0) => f.debug_tuple("Add").field(arg0).finish(),
             Self::Remove(arg0) => f.debug_tuple("Remove").field(arg0).finish(),
+            Self::Classes { add, remove } => f
+                .debug_struct("Classes")
+                .field("add", add)
+                .field("remove", remove)
+                .finish(),
+            Self::Styles { set, remove } => f
+                .debug_struct("Styles")
+                .field("set", set)
+                .field("remove", remove)
+                .finish(),
         }
     }
 }
@@ -32,8 +53,17 @@ impl<Msg> Debug for AttrPatch<Msg> {
 pub enum Patch<Msg> {
     Add(Html<Msg>),
     Replace(Html<Msg>),
+    /// Changes the content of a text node, which keeps the node in place.
+    Text(String),
+    /// Changes the markup inside of the element of an [Html::UnsafeRawHtml].
+    RawHtml(String),
     Update(Vec<Patch<Msg>>, Vec<AttrPatch<Msg>>),
     Remove(Option<String>),
+    /// Applies the patch to the old child at the index, and moves it to this position unless the
+    /// patch removes it. Used for children that are matched by their key.
+    Move(usize, Box<Patch<Msg>>),
+    /// Renders a new child at this position, between keyed children.
+    Insert(Html<Msg>),
     Nothing,
 }
 
@@ -48,29 +78,73 @@ impl<Msg> Debug for Patch<Msg> {
         match self {
             Self::Add(arg0) => f.debug_tuple("Add").field(arg0).finish(),
             Self::Replace(arg0) => f.debug_tuple("Replace").field(arg0).finish(),
+            Self::Text(arg0) => f.debug_tuple("Text").field(arg0).finish(),
+            Self::RawHtml(arg0) => f.debug_tuple("RawHtml").field(arg0).finish(),
             Self::Update(arg0, arg1) => f.debug_tuple("Update").field(arg0).field(arg1).finish(),
             Self::Remove(arg0) => f.debug_tuple("Remove").field(arg0).finish(),
+            Self::Move(arg0, arg1) => f.debug_tuple("Move").field(arg0).field(arg1).finish(),
+            Self::Insert(arg0) => f.debug_tuple("Insert").field(arg0).finish(),
             Self::Nothing => write!(f, "Nothing"),
         }
     }
 }
 
-/// Applies a sequence of pathes for children.
-fn apply_children<Msg: 'static + Send + Sync>(
-    parent: dom::Element,
-    children: HtmlCollection,
+/// Applies a sequence of patches for the children of `parent`, skipping the first `offset` nodes
+/// that are not managed by the virtual dom. The children are collected before patching, so
+/// removals don't shift the nodes that the next patches refer to.
+pub(crate) fn apply_children<Msg: 'static + Send + Sync>(
+    parent: &dom::Element,
+    offset: u32,
     patches: Vec<Patch<Msg>>,
     context: &mut Context<'_, Msg>,
 ) {
+    let nodes = parent.child_nodes();
+    let children: Vec<_> = (offset..nodes.length())
+        .filter_map(|i| nodes.get(i))
+        .collect();
+
     for (i, patch) in patches.into_iter().enumerate() {
-        if let Some(child) = children.get_with_index(i as u32) {
-            patch.apply(child, context);
-        } else {
-            patch.apply(parent.clone(), context);
+        // Keyed children are placed in order, so the ones before `i` are already in place.
+        let position = offset + i as u32;
+
+        match patch {
+            Patch::Move(from, patch) => {
+                let Some(child) = children.get(from) else {
+                    continue;
+                };
+                let removed = matches!(*patch, Patch::Remove(_));
+
+                patch.apply(child.clone(), context);
+
+                if !removed {
+                    place(parent, child, position);
+                }
+            }
+            Patch::Insert(html) => {
+                if let Some(child) = html.render(parent.clone(), context) {
+                    place(parent, &child, position);
+                }
+            }
+            patch => {
+                if let Some(child) = children.get(i) {
+                    patch.apply(child.clone(), context);
+                } else {
+                    patch.apply(parent.clone().into(), context);
+                }
+            }
         }
     }
 }
 
+/// Moves `child` to `position` in the children of `parent`, unless it's already there.
+fn place(parent: &dom::Element, child: &dom::Node, position: u32) {
+    let current = parent.child_nodes().get(position);
+
+    if current.as_ref() != Some(child) {
+        parent.insert_before(child, current.as_ref()).unwrap();
+    }
+}
+
 /// Applies a sequence of patches for a sequence of attributes.
 fn apply_attributes<Msg: 'static + Send + Sync>(
     el: dom::Element,
@@ -82,17 +156,54 @@ fn apply_attributes<Msg: 'static + Send + Sync>(
             AttrPatch::Add(add) => {
                 add.render(el.clone(), context);
             }
+            AttrPatch::Classes { add, remove } => {
+                let classes = el.class_list();
+
+                for class in remove {
+                    classes.remove_1(&class).unwrap();
+                }
+
+                for class in add {
+                    classes.add_1(&class).unwrap();
+                }
+            }
+            AttrPatch::Styles { set, remove } => {
+                // Also works for SVG and MathML elements, which have a `style` too.
+                let style = el.unchecked_ref::<HtmlElement>().style();
+
+                for name in remove {
+                    style.remove_property(&name).unwrap();
+                }
+
+                for (name, value) in set {
+                    style.set_property(&name, &value).unwrap();
+                }
+            }
             AttrPatch::Remove(rem) => match rem {
-                Attribute::OnClick(_) => el.dyn_ref::<HtmlElement>().unwrap().set_onclick(None),
-                Attribute::Custom(n, _) => el.set_attribute(&n, "").unwrap(),
+                Attribute::OnClick(_) => set_handler(&el, "click", None),
+                Attribute::OnInput(_) => set_handler(&el, "input", None),
+                Attribute::OnChange(_) => set_handler(&el, "change", None),
+                Attribute::Custom(n, _) | Attribute::Bool(n, _) => el.remove_attribute(&n).unwrap(),
+                Attribute::Class(_) => el.remove_attribute("class").unwrap(),
+                Attribute::Style(_) => el.remove_attribute("style").unwrap(),
+                Attribute::Property(n, value) => {
+                    // Properties can't be removed, so they go back to the empty value of their type.
+                    let empty = match value {
+                        PropertyValue::String(_) => PropertyValue::String(String::new()),
+                        PropertyValue::Bool(_) => PropertyValue::Bool(false),
+                        PropertyValue::Number(_) => PropertyValue::Number(0.0),
+                    };
+                    set_property(&el, &n, property_value(&empty))
+                }
                 Attribute::OnMount(_) => (),
                 Attribute::OnUnmount(ev) => {
-                    let ev = ev.clone();
-                    let context = context.channel.clone();
+                    if let Some(ev) = ev.fire() {
+                        let context = context.channel.clone();
 
-                    let ev_future = async move { context.clone().send(ev).await };
+                        let ev_future = async move { context.clone().send(ev).await };
 
-                    futures::executor::block_on(ev_future).unwrap();
+                        futures::executor::block_on(ev_future).unwrap();
+                    }
                 }
             },
         }
@@ -100,29 +211,41 @@ fn apply_attributes<Msg: 'static + Send + Sync>(
 }
 
 impl<'a, Msg: 'static + Send + Sync> Patch<Msg> {
-    /// This function applies a patch to the real dom.
-    pub fn apply(self, el: dom::Element, context: &mut Context<'a, Msg>) {
+    /// This function applies a patch to the real dom. `Add` patches receive the parent node and
+    /// the other ones receive the node that they change.
+    pub fn apply(self, node: dom::Node, context: &mut Context<'a, Msg>) {
         match self {
-            Patch::Add(add) => {
-                if let Some(new_el) = add.render(el.clone(), context) {
-                    el.append_child(&new_el).unwrap();
+            Patch::Add(add) | Patch::Insert(add) => {
+                let parent = node.unchecked_into::<dom::Element>();
+
+                if let Some(new_node) = add.render(parent.clone(), context) {
+                    parent.append_child(&new_node).unwrap();
                 }
             }
             Patch::Replace(replace) => {
-                if let Some(new_el) = replace.render(el.clone(), context) {
-                    el.replace_with_with_node_1(&new_el).unwrap();
+                let parent = node.parent_element().unwrap();
+
+                if let Some(new_node) = replace.render(parent.clone(), context) {
+                    parent.replace_child(&new_node, &node).unwrap();
                 }
             }
+            Patch::Text(text) => node.set_node_value(Some(&text)),
+            Patch::RawHtml(html) => node.unchecked_into::<dom::Element>().set_inner_html(&html),
             Patch::Update(children, attr) => {
-                apply_children(el.clone(), el.children(), children, context);
+                let el = node.unchecked_into::<dom::Element>();
+                apply_children(&el, 0, children, context);
                 apply_attributes(el, attr, context);
             }
             Patch::Remove(key) => {
                 if let Some((on_mount, key)) = context.on_mount.as_ref().zip(key.as_ref()) {
-                    on_mount(el.clone(), key.clone());
+                    on_mount(node.clone().unchecked_into(), key.clone());
+                }
+
+                if let Some(parent) = node.parent_node() {
+                    parent.remove_child(&node).unwrap();
                 }
-                el.remove()
             }
+            Patch::Move(_, patch) => patch.apply(node, context),
             Patch::Nothing => (),
         }
     }
@@ -0,0 +1,114 @@
+//! Ports are named channels between the application and JavaScript, in the same spirit as the
+//! ports of Elm. Outgoing ports are commands that serialize a value and hand it to every JS
+//! callback subscribed to the port, while incoming ports turn values sent from JS into messages.
+//!
+//! On the JS side the generated module exposes `subscribePort` and `sendToPort`:
+//!
+//! ```js
+//! import init, { subscribePort, sendToPort } from "./static/roko_sofia_client.js";
+//!
+//! await init();
+//! subscribePort("analytics", (event) => track(event));
+//! sendToPort("editor", { content: "Hello" });
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use js_sys::Function;
+use roko_macro::cmd;
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::Channel;
+
+type Handler = Box<dyn Fn(JsValue) -> Result<(), JsValue>>;
+
+thread_local! {
+    static OUTGOING: RefCell<HashMap<String, Vec<Function>>> = RefCell::new(HashMap::new());
+    static INCOMING: RefCell<HashMap<String, Handler>> = RefCell::new(HashMap::new());
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_PORTS: &'static str = r#"
+/**
+ * Registers a callback that receives every value sent through the outgoing port `name`.
+ */
+export function subscribePort<T = unknown>(name: string, callback: (value: T) => void): void;
+
+/**
+ * Sends a value to the incoming port `name`. Throws if the port does not exist or the value
+ * cannot be decoded into the type expected by the application.
+ */
+export function sendToPort<T = unknown>(name: string, value: T): void;
+"#;
+
+/// Registers a JS callback for the outgoing port `name`.
+#[wasm_bindgen(js_name = subscribePort, skip_typescript)]
+pub fn subscribe_port(name: &str, callback: Function) {
+    OUTGOING.with(|ports| {
+        ports
+            .borrow_mut()
+            .entry(name.to_string())
+            .or_default()
+            .push(callback)
+    });
+}
+
+/// Sends a JS value to the incoming port `name`.
+#[wasm_bindgen(js_name = sendToPort, skip_typescript)]
+pub fn send_to_port(name: &str, value: JsValue) -> Result<(), JsValue> {
+    INCOMING.with(|ports| match ports.borrow().get(name) {
+        Some(handler) => handler(value),
+        None => Err(JsValue::from_str(&format!(
+            "port '{name}' is not subscribed"
+        ))),
+    })
+}
+
+/// Outgoing port. Serializes the value and calls every JS callback subscribed to `port`. The
+/// command never produces a message.
+#[cmd]
+pub async fn send<Msg: 'static, T: Serialize + 'static>(
+    port: &'static str,
+    value: T,
+) -> Option<Msg> {
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+
+    let value = match value.serialize(&serializer) {
+        Ok(value) => value,
+        Err(err) => {
+            web_sys::console::error_2(&JsValue::from_str(port), &err.into());
+            return None;
+        }
+    };
+
+    let callbacks = OUTGOING.with(|ports| ports.borrow().get(port).cloned().unwrap_or_default());
+
+    for callback in callbacks {
+        if let Err(err) = callback.call1(&JsValue::NULL, &value) {
+            web_sys::console::error_2(&JsValue::from_str(port), &err);
+        }
+    }
+
+    None
+}
+
+/// Incoming port. Every value sent from JS to `port` is decoded and turned into a message with
+/// `on_value`. It's meant to be called from the subscriptions function.
+pub fn subscribe<Msg: 'static, T: DeserializeOwned + 'static>(
+    channel: Channel<Msg>,
+    port: &str,
+    on_value: fn(T) -> Msg,
+) {
+    let handler: Handler = Box::new(move |value| {
+        let value: T = serde_wasm_bindgen::from_value(value)?;
+
+        channel
+            .unbounded_send(Arc::new(on_value(value)))
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    });
+
+    INCOMING.with(|ports| ports.borrow_mut().insert(port.to_string(), handler));
+}
@@ -1,16 +1,20 @@
 //! This module renders the virtual dom to the real dom. The main structure of this module is the
 //! [Render] trait that is implemented for all the types that can be rendered to the real dom.
 
-use roko_html::{Attribute, Html, Node};
+use roko_html::{
+    attribute_namespace, style_string, Attribute, Html, InputEvent, Listener, Namespace, Node,
+    PropertyValue,
+};
 
-use dom::HtmlElement;
 use futures::channel::mpsc::UnboundedSender;
 use futures::SinkExt;
+use std::rc::Rc;
 use std::sync::Arc;
-use wasm_bindgen::prelude::Closure;
-use wasm_bindgen::JsCast;
+use wasm_bindgen::{JsCast, JsValue};
 use web_sys as dom;
 
+use crate::events::{set_handler, EventHandler};
+
 fn window() -> dom::Window {
     web_sys::window().expect("no global `window` exists")
 }
@@ -21,63 +25,140 @@ fn document() -> dom::Document {
         .expect("should have a document on window")
 }
 
+pub(crate) fn property_value(value: &PropertyValue) -> JsValue {
+    match value {
+        PropertyValue::String(value) => JsValue::from_str(value),
+        PropertyValue::Bool(value) => JsValue::from_bool(*value),
+        PropertyValue::Number(value) => JsValue::from_f64(*value),
+    }
+}
+
+/// Sets a JS property of the element. It's only written when the live value differs, so updating
+/// an input with what the user just typed doesn't move the cursor.
+pub(crate) fn set_property(element: &dom::Element, name: &str, value: JsValue) {
+    let name = JsValue::from_str(name);
+
+    if js_sys::Reflect::get(element, &name).ok().as_ref() != Some(&value) {
+        js_sys::Reflect::set(element, &name, &value).unwrap();
+    }
+}
+
+/// Sets an attribute, prefixed ones like `xlink:href` are set in their namespace.
+fn set_attribute(element: &dom::Element, name: &str, value: &str) {
+    match attribute_namespace(name) {
+        Some(namespace) => element.set_attribute_ns(Some(namespace), name, value),
+        None => element.set_attribute(name, value),
+    }
+    .unwrap()
+}
+
+/// Namespace that the children of `parent` are created in. Elements like `<a>` or `<title>` exist
+/// in more than one namespace, so inside of an `<svg>` or a `<math>` they take the namespace of
+/// their parent, like the HTML parser does, until a `<foreignObject>` goes back to HTML.
+fn inherited_namespace(parent: &dom::Element) -> Namespace {
+    let namespace = parent
+        .namespace_uri()
+        .and_then(|uri| Namespace::from_uri(&uri))
+        .unwrap_or_default();
+
+    match (namespace, parent.local_name().as_str()) {
+        (Namespace::Svg, "foreignObject") | (Namespace::MathMl, "annotation-xml") => {
+            Namespace::Html
+        }
+        (namespace, _) => namespace,
+    }
+}
+
+/// Reads the state of a form element for an [InputEvent]. Properties that the element doesn't
+/// have are left empty.
+fn input_event(element: &dom::Element) -> InputEvent {
+    let property = |name| js_sys::Reflect::get(element, &JsValue::from_str(name)).ok();
+
+    InputEvent {
+        value: property("value")
+            .and_then(|value| value.as_string())
+            .unwrap_or_default(),
+        checked: property("checked")
+            .and_then(|checked| checked.as_bool())
+            .unwrap_or_default(),
+    }
+}
+
+/// Handler of an event that reads the element it's attached to.
+fn listen<Msg: 'static>(listener: &Listener<Msg>, context: &mut Context<'_, Msg>) -> EventHandler {
+    let listener = listener.clone();
+    let channel = context.channel.clone();
+
+    Rc::new(move |event| {
+        let Some(element) = event.current_target() else {
+            return;
+        };
+
+        if let Some(msg) = listener.fire(input_event(element.unchecked_ref())) {
+            channel.unbounded_send(msg).unwrap();
+        }
+    })
+}
+
 pub struct Context<'a, Msg> {
     pub channel: UnboundedSender<Arc<Msg>>,
     pub on_mount: &'a Option<Box<dyn Fn(dom::Element, String)>>,
     pub on_unmount: &'a Option<Box<dyn Fn(dom::Element, String)>>,
 }
 
-/// Trait for rendering a virtual dom to the real dom.
+/// Trait for rendering a virtual dom to the real dom. It returns the node that has to be inserted
+/// in the container, if there's one.
 pub trait Render<'a, T> {
-    fn render(&self, container: dom::Element, ctx: &mut Context<'a, T>) -> Option<dom::Element>;
+    fn render(&self, container: dom::Element, ctx: &mut Context<'a, T>) -> Option<dom::Node>;
 }
 
 impl<'a, Msg: 'static> Render<'a, Msg> for String {
-    fn render(&self, container: dom::Element, _: &mut Context<'a, Msg>) -> Option<dom::Element> {
-        container.set_text_content(Some(self));
-        None
+    fn render(&self, _: dom::Element, _: &mut Context<'a, Msg>) -> Option<dom::Node> {
+        Some(document().create_text_node(self).into())
     }
 }
 
 impl<'a, Msg: 'static + Send + Sync> Render<'a, Msg> for Attribute<Msg> {
-    fn render(
-        &self,
-        container: dom::Element,
-        context: &mut Context<'a, Msg>,
-    ) -> Option<dom::Element> {
+    fn render(&self, container: dom::Element, context: &mut Context<'a, Msg>) -> Option<dom::Node> {
         match self {
             Attribute::OnClick(click) => {
                 let click = click.clone();
-
                 let channel = context.channel.clone();
 
-                let data: Box<dyn FnMut()> = Box::new(move || {
-                    let click = click.clone();
-
-                    let channel = channel.clone();
-
-                    let click_future = async move { channel.clone().send(click).await };
-
-                    futures::executor::block_on(click_future).unwrap()
+                let handler: EventHandler = Rc::new(move |_| {
+                    if let Some(msg) = click.fire() {
+                        channel.unbounded_send(msg).unwrap();
+                    }
                 });
 
-                let closure = Closure::wrap(data);
-
-                container
-                    .dyn_ref::<HtmlElement>()
-                    .unwrap()
-                    .set_onclick(Some(closure.as_ref().unchecked_ref()));
-
-                closure.forget()
+                set_handler(&container, "click", Some(handler));
+            }
+            Attribute::OnInput(listener) => {
+                set_handler(&container, "input", Some(listen(listener, context)))
+            }
+            Attribute::OnChange(listener) => {
+                set_handler(&container, "change", Some(listen(listener, context)))
+            }
+            Attribute::Custom(name, value) => set_attribute(&container, name, value),
+            Attribute::Bool(name, true) => set_attribute(&container, name, ""),
+            Attribute::Bool(name, false) => container.remove_attribute(name).unwrap(),
+            Attribute::Class(classes) => container
+                .set_attribute("class", &classes.join(" "))
+                .unwrap(),
+            Attribute::Style(declarations) => container
+                .set_attribute("style", &style_string(declarations))
+                .unwrap(),
+            Attribute::Property(name, value) => {
+                set_property(&container, name, property_value(value))
             }
-            Attribute::Custom(name, value) => container.set_attribute(name, value).unwrap(),
             Attribute::OnMount(ev) => {
-                let ev = ev.clone();
-                let channel = context.channel.clone();
+                if let Some(ev) = ev.fire() {
+                    let channel = context.channel.clone();
 
-                let ev_future = async move { channel.clone().send(ev).await };
+                    let ev_future = async move { channel.clone().send(ev).await };
 
-                futures::executor::block_on(ev_future).unwrap()
+                    futures::executor::block_on(ev_future).unwrap()
+                }
             }
             Attribute::OnUnmount(_) => (),
         };
@@ -86,8 +167,15 @@ impl<'a, Msg: 'static + Send + Sync> Render<'a, Msg> for Attribute<Msg> {
 }
 
 impl<'a, Msg: 'static + Send + Sync> Render<'a, Msg> for Node<Msg> {
-    fn render(&self, _: dom::Element, context: &mut Context<'a, Msg>) -> Option<dom::Element> {
-        let element = document().create_element(self.tag).unwrap();
+    fn render(&self, parent: dom::Element, context: &mut Context<'a, Msg>) -> Option<dom::Node> {
+        let namespace = match self.namespace {
+            Namespace::Html => inherited_namespace(&parent),
+            namespace => namespace,
+        };
+
+        let element = document()
+            .create_element_ns(Some(namespace.uri()), self.tag)
+            .unwrap();
 
         if let Some((on_mount, id)) = context.on_mount.as_ref().zip(self.id.as_ref()) {
             on_mount(element.clone(), id.clone());
@@ -103,19 +191,36 @@ impl<'a, Msg: 'static + Send + Sync> Render<'a, Msg> for Node<Msg> {
             }
         }
 
-        Some(element)
+        Some(element.into())
     }
 }
 
 impl<'a, Msg: 'static + Send + Sync> Render<'a, Msg> for Html<Msg> {
-    fn render(
-        &self,
-        container: dom::Element,
-        context: &mut Context<'a, Msg>,
-    ) -> Option<dom::Element> {
+    fn render(&self, container: dom::Element, context: &mut Context<'a, Msg>) -> Option<dom::Node> {
         match self {
             Html::Node(node) => node.render(container, context),
             Html::Text(text) => text.render(container, context),
+            Html::UnsafeRawHtml(html) => {
+                // The markup can have any number of nodes, so it's wrapped in an element that
+                // doesn't generate a box to keep one node per entry of the virtual dom.
+                let element = document().create_element("roko-raw").unwrap();
+                element.set_attribute("style", "display: contents").unwrap();
+                element.set_inner_html(html);
+                Some(element.into())
+            }
+            Html::Fragment(children) => {
+                for child in children {
+                    if let Some(result) = child.render(container.clone(), context) {
+                        container.append_child(&result).unwrap();
+                    }
+                }
+                None
+            }
+            Html::Provider(_) | Html::Consumer(_) | Html::Map(_) => {
+                // Contexts and maps are resolved before rendering, if one is left it's skipped.
+                debug_assert!(false, "the tree wasn't resolved before rendering");
+                None
+            }
         }
     }
 }
@@ -0,0 +1,56 @@
+//! Ports need a JS runtime, run them with `wasm-pack test --node crates/roko-dom`.
+#![cfg(target_arch = "wasm32")]
+
+use futures::channel::mpsc;
+use js_sys::{Function, Reflect, JSON};
+use roko_dom::ports;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[derive(Debug, PartialEq)]
+enum Msg {
+    Edited(String),
+}
+
+#[wasm_bindgen_test]
+fn sends_values_to_every_subscriber() {
+    ports::subscribe_port(
+        "analytics",
+        Function::new_with_args("v", "globalThis.first = v"),
+    );
+    ports::subscribe_port(
+        "analytics",
+        Function::new_with_args("v", "globalThis.second = v"),
+    );
+
+    let msg = futures::executor::block_on(ports::send::<Msg, _>("analytics", ("open", 2)));
+    assert_eq!(msg, None);
+
+    for name in ["first", "second"] {
+        let value = Reflect::get(&js_sys::global(), &JsValue::from_str(name)).unwrap();
+        assert_eq!(JSON::stringify(&value).unwrap(), "[\"open\",2]");
+    }
+}
+
+#[wasm_bindgen_test]
+fn turns_incoming_values_into_messages() {
+    let (sender, mut receiver) = mpsc::unbounded();
+    ports::subscribe(sender, "editor", Msg::Edited);
+
+    ports::send_to_port("editor", JsValue::from_str("Hello")).unwrap();
+
+    let msg = receiver.try_recv().unwrap();
+    assert_eq!(*msg, Msg::Edited("Hello".to_string()));
+}
+
+#[wasm_bindgen_test]
+fn rejects_unknown_ports_and_invalid_values() {
+    let (sender, _receiver) = mpsc::unbounded();
+    ports::subscribe(sender, "title", Msg::Edited);
+
+    assert_eq!(
+        ports::send_to_port("missing", JsValue::NULL).unwrap_err(),
+        "port 'missing' is not subscribed"
+    );
+    assert!(ports::send_to_port("title", JsValue::from_f64(1.0)).is_err());
+}
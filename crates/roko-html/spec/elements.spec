@@ -0,0 +1,256 @@
+# Elements and attributes that `html!` accepts and `roko_dom` generates constructors and attribute
+# helpers for. It follows the HTML living standard, SVG 2 and MathML Core.
+#
+# A `[section]` starts a group: `[global]` and `[aria]` are allowed on every element, `[html]`,
+# `[svg]` and `[mathml]` list the elements of each namespace, one per line with the attributes
+# after a colon. The `*` entry of a namespace is allowed on all of its elements. Indented lines
+# continue the line above.
+#
+# `name?` is a boolean attribute, its presence is the value.
+# `name!` is set as a property, since the attribute is only the initial state, e.g. `checked?!`.
+
+[global]
+accesskey autocapitalize autocorrect autofocus? class contenteditable dir draggable enterkeyhint
+    exportparts hidden? id inert? inputmode is itemid itemprop itemref itemscope? itemtype lang
+    nonce part popover slot spellcheck style tabindex title translate writingsuggestions
+
+[aria]
+role aria-activedescendant aria-atomic aria-autocomplete aria-braillelabel
+    aria-brailleroledescription aria-busy aria-checked aria-colcount aria-colindex
+    aria-colindextext aria-colspan aria-controls aria-current aria-describedby aria-description
+    aria-details aria-disabled aria-errormessage aria-expanded aria-flowto aria-haspopup
+    aria-hidden aria-invalid aria-keyshortcuts aria-label aria-labelledby aria-level aria-live
+    aria-modal aria-multiline aria-multiselectable aria-orientation aria-owns aria-placeholder
+    aria-posinset aria-pressed aria-readonly aria-relevant aria-required aria-roledescription
+    aria-rowcount aria-rowindex aria-rowindextext aria-rowspan aria-selected aria-setsize aria-sort
+    aria-valuemax aria-valuemin aria-valuenow aria-valuetext
+
+[html]
+# Document metadata
+html
+head
+title
+base: href target
+link: as blocking color crossorigin disabled? fetchpriority href hreflang imagesizes imagesrcset
+    integrity media referrerpolicy rel sizes type
+meta: charset content http-equiv media name
+style: blocking media
+
+# Sections
+body
+article
+section
+nav
+aside
+h1
+h2
+h3
+h4
+h5
+h6
+hgroup
+header
+footer
+address
+
+# Grouping content
+p
+hr
+pre
+blockquote: cite
+ol: reversed? start type
+ul
+menu
+li: value
+dl
+dt
+dd
+figure
+figcaption
+main
+search
+div
+
+# Text-level semantics
+a: download href hreflang ping referrerpolicy rel target type
+em
+strong
+small
+s
+cite
+q: cite
+dfn
+abbr
+ruby
+rt
+rp
+data: value
+time: datetime
+code
+var
+samp
+kbd
+sub
+sup
+i
+b
+u
+mark
+bdi
+bdo
+span
+br
+wbr
+
+# Edits
+ins: cite datetime
+del: cite datetime
+
+# Embedded content
+picture
+source: height media sizes src srcset type width
+img: alt crossorigin decoding fetchpriority height ismap? loading referrerpolicy sizes src srcset
+    usemap width
+iframe: allow allowfullscreen? height loading name referrerpolicy sandbox src srcdoc width
+embed: height src type width
+object: data form height name type width
+video: autoplay? controls? crossorigin height loop? muted? playsinline? poster preload src width
+audio: autoplay? controls? crossorigin loop? muted? preload src
+track: default? kind label src srclang
+map: name
+area: alt coords download href ping referrerpolicy rel shape target
+
+# Tabular data
+table
+caption
+colgroup: span
+col: span
+tbody
+thead
+tfoot
+tr
+td: colspan headers rowspan
+th: abbr colspan headers rowspan scope
+
+# Forms, `value` of a select is only a property.
+form: accept-charset action autocomplete enctype method name novalidate? rel target
+label: for
+input: accept alpha? alt autocomplete checked?! colorspace dirname disabled? form formaction
+    formenctype formmethod formnovalidate? formtarget height list max maxlength min minlength
+    multiple? name pattern placeholder popovertarget popovertargetaction readonly? required? size
+    src step type value! width
+button: command commandfor disabled? form formaction formenctype formmethod formnovalidate?
+    formtarget name popovertarget popovertargetaction type value
+select: autocomplete disabled? form multiple? name required? size value!
+datalist
+optgroup: disabled? label
+option: disabled? label selected?! value!
+textarea: autocomplete cols dirname disabled? form maxlength minlength name placeholder readonly?
+    required? rows value! wrap
+output: for form name
+progress: max value
+meter: high low max min optimum value
+fieldset: disabled? form name
+legend
+
+# Interactive elements
+details: name open?
+summary
+dialog: open?
+
+# Scripting
+script: async? blocking crossorigin defer? fetchpriority integrity nomodule? referrerpolicy src
+    type
+noscript
+template: shadowrootclonable? shadowrootdelegatesfocus? shadowrootmode shadowrootserializable?
+slot: name
+canvas: height width
+
+[svg]
+# Presentation and conditional processing attributes.
+*: alignment-baseline baseline-shift clip-path clip-rule color color-interpolation
+    color-interpolation-filters cursor direction display dominant-baseline fill fill-opacity
+    fill-rule filter flood-color flood-opacity font-family font-size font-size-adjust font-stretch
+    font-style font-variant font-weight image-rendering letter-spacing lighting-color marker-end
+    marker-mid marker-start mask mask-type opacity overflow paint-order pointer-events
+    requiredExtensions shape-rendering stop-color stop-opacity stroke stroke-dasharray
+    stroke-dashoffset stroke-linecap stroke-linejoin stroke-miterlimit stroke-opacity stroke-width
+    systemLanguage text-anchor text-decoration text-rendering transform transform-origin
+    unicode-bidi vector-effect visibility word-spacing writing-mode xml:lang xml:space
+
+# Structure
+svg: height preserveAspectRatio version viewBox width x xmlns xmlns:xlink y
+g
+defs
+symbol: height preserveAspectRatio refX refY viewBox width x y
+use: height href width x xlink:href y
+desc
+view: preserveAspectRatio viewBox
+foreignObject: height width x y
+
+# Shapes
+path: d pathLength
+circle: cx cy pathLength r
+ellipse: cx cy pathLength rx ry
+line: pathLength x1 x2 y1 y2
+polyline: pathLength points
+polygon: pathLength points
+rect: height pathLength rx ry width x y
+
+# Text
+text: dx dy lengthAdjust rotate textLength x y
+tspan: dx dy lengthAdjust rotate textLength x y
+textPath: href lengthAdjust method path side spacing startOffset textLength xlink:href
+
+# Paint servers and embedded content
+image: crossorigin decoding height href preserveAspectRatio width x xlink:href y
+marker: markerHeight markerUnits markerWidth orient preserveAspectRatio refX refY viewBox
+pattern: height href patternContentUnits patternTransform patternUnits preserveAspectRatio
+    viewBox width x y
+mask: height maskContentUnits maskUnits width x y
+clipPath: clipPathUnits
+linearGradient: gradientTransform gradientUnits href spreadMethod x1 x2 y1 y2
+radialGradient: cx cy fr fx fy gradientTransform gradientUnits href r spreadMethod
+stop: offset
+
+# Filters
+filter: filterUnits height primitiveUnits width x y
+feBlend: height in in2 mode result width x y
+feColorMatrix: height in result type values width x y
+feComposite: height in in2 k1 k2 k3 k4 operator result width x y
+feFlood: height result width x y
+feGaussianBlur: edgeMode height in result stdDeviation width x y
+feMerge: height result width x y
+feMergeNode: in
+feOffset: dx dy height in result width x y
+
+# Animation
+animate: accumulate additive attributeName begin by calcMode dur end from href keySplines
+    keyTimes max min repeatCount repeatDur restart to values
+animateTransform: accumulate additive attributeName begin by calcMode dur end from href
+    keySplines keyTimes max min repeatCount repeatDur restart to type values
+
+[mathml]
+*: displaystyle mathbackground mathcolor mathsize mathvariant scriptlevel
+math: alttext display
+mi
+mn
+mo: fence form largeop lspace maxsize minsize movablelimits rspace separator stretchy symmetric
+ms
+mspace: depth height width
+mtext
+mrow
+mfrac: linethickness
+msqrt
+mroot
+msub
+msup
+msubsup
+munder: accentunder
+mover: accent
+munderover: accent accentunder
+mtable
+mtr
+mtd: columnspan rowspan
+semantics
+annotation: encoding
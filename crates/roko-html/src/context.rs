@@ -0,0 +1,231 @@
+//! Context values for deep component trees. A [Provider] node makes a value available to every
+//! [Consumer] below it, so things like the theme or the current user don't have to be passed
+//! through every component. Consumers are lazy: they are turned into regular nodes by
+//! [Html::resolve], which the runtime calls on every view, so a new value re-renders them.
+
+use std::any::{type_name, Any, TypeId};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::map::{Convert, Same};
+use crate::{Attrs, Children, Html, Node};
+
+/// Node that makes `value` available to the consumers inside of its children. Like a fragment, it
+/// doesn't exist in the DOM and its children are placed directly in the parent.
+pub struct Provider<Msg> {
+    pub type_id: TypeId,
+    pub value: Arc<dyn Any>,
+    pub children: Vec<Html<Msg>>,
+}
+
+/// View of a consumer, receiving the value of its provider.
+pub type ConsumerView<Msg> = Arc<dyn Fn(&dyn Any) -> Html<Msg>>;
+
+/// Node that renders a view with the value of the closest provider of the same type.
+pub struct Consumer<Msg> {
+    pub type_id: TypeId,
+    pub type_name: &'static str,
+    /// Value of the consumer when it has no provider.
+    pub default: fn() -> Arc<dyn Any>,
+    pub view: ConsumerView<Msg>,
+}
+
+impl<Msg> Debug for Provider<Msg> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Provider")
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+impl<Msg> Debug for Consumer<Msg> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Consumer")
+            .field("type_name", &self.type_name)
+            .finish()
+    }
+}
+
+impl<Msg> Clone for Provider<Msg> {
+    fn clone(&self) -> Self {
+        Self {
+            type_id: self.type_id,
+            value: self.value.clone(),
+            children: self.children.clone(),
+        }
+    }
+}
+
+impl<Msg> Clone for Consumer<Msg> {
+    fn clone(&self) -> Self {
+        Self {
+            type_id: self.type_id,
+            type_name: self.type_name,
+            default: self.default,
+            view: self.view.clone(),
+        }
+    }
+}
+
+/// Element function that provides `value` to its children, e.g.
+/// `<context::provider model={theme}> ... </context::provider>`.
+pub fn provider<T: 'static, Msg>(
+    value: T,
+    _id: Option<String>,
+    _attrs: Attrs<Msg>,
+    children: Children<Msg>,
+) -> Html<Msg> {
+    Html::Provider(Provider {
+        type_id: TypeId::of::<T>(),
+        value: Arc::new(value),
+        children,
+    })
+}
+
+/// Renders `view` with the value of the closest [provider] of type `T`, or with `T::default()`
+/// when there is none.
+pub fn consume<T: Default + 'static, Msg>(view: impl Fn(&T) -> Html<Msg> + 'static) -> Html<Msg> {
+    Html::Consumer(Consumer {
+        type_id: TypeId::of::<T>(),
+        type_name: type_name::<T>(),
+        default: || Arc::new(T::default()),
+        view: Arc::new(move |value| view(value.downcast_ref().unwrap())),
+    })
+}
+
+pub(crate) type Scope = Vec<(TypeId, Arc<dyn Any>)>;
+
+impl<Msg: Clone + 'static> Html<Msg> {
+    /// Replaces every consumer with the view of its value, every provider with its children and
+    /// every map with its view, leaving a tree that can be rendered and diffed. Fragments are
+    /// flattened along the way, so only the root can still be one.
+    pub fn resolve(self) -> Html<Msg> {
+        let mut resolved = Vec::new();
+        self.resolve_with(&mut Vec::new(), &Same, &mut resolved);
+
+        if resolved.len() == 1 {
+            resolved.pop().unwrap()
+        } else {
+            Html::Fragment(resolved)
+        }
+    }
+}
+
+impl<Msg> Html<Msg> {
+    /// Resolves the tree into `out`, with `convert` turning its handlers and maps into the ones of
+    /// the result.
+    pub(crate) fn resolve_with<Out>(
+        self,
+        scope: &mut Scope,
+        convert: &impl Convert<Msg, Out>,
+        out: &mut Vec<Html<Out>>,
+    ) {
+        match self {
+            Html::Node(node) => {
+                let mut children = Vec::with_capacity(node.children.len());
+
+                for child in node.children {
+                    child.resolve_with(scope, convert, &mut children);
+                }
+
+                out.push(Html::Node(Node {
+                    tag: node.tag,
+                    namespace: node.namespace,
+                    id: node.id,
+                    attributes: convert.attributes(node.attributes),
+                    children,
+                }));
+            }
+            Html::Text(text) => out.push(Html::Text(text)),
+            Html::UnsafeRawHtml(html) => out.push(Html::UnsafeRawHtml(html)),
+            Html::Fragment(children) => {
+                for child in children {
+                    child.resolve_with(scope, convert, out);
+                }
+            }
+            Html::Provider(provider) => {
+                scope.push((provider.type_id, provider.value));
+
+                for child in provider.children {
+                    child.resolve_with(scope, convert, out);
+                }
+
+                scope.pop();
+            }
+            Html::Consumer(consumer) => {
+                let value = scope
+                    .iter()
+                    .rev()
+                    .find(|(type_id, _)| *type_id == consumer.type_id)
+                    .map_or_else(consumer.default, |(_, value)| value.clone());
+
+                (consumer.view)(value.as_ref()).resolve_with(scope, convert, out)
+            }
+            Html::Map(map) => convert.map(map, scope, out),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{text, unsafe_raw_html};
+
+    fn label(html: Html<()>) -> String {
+        match html.resolve() {
+            Html::Text(text) => text,
+            other => panic!("expected a text, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn uses_the_closest_provider() {
+        let consumer = consume(|n: &i32| text(n.to_string()));
+        let inner = provider(2, None, Vec::new(), vec![consumer]);
+
+        assert_eq!(label(provider(1, None, Vec::new(), vec![inner])), "2");
+    }
+
+    #[test]
+    fn falls_back_to_the_default_value() {
+        assert_eq!(label(consume(|n: &i32| text(n.to_string()))), "0");
+    }
+
+    #[test]
+    fn flattens_fragments() {
+        let nested = Html::Fragment(vec![text("b"), Html::Fragment(vec![text("c")])]);
+        let list = Html::<()>::node("ul", None, Vec::new(), vec![text("a"), nested]);
+
+        let Html::Node(node) = list.resolve() else {
+            panic!("expected a node");
+        };
+        assert_eq!(
+            format!("{:?}", node.children),
+            "[Text(\"a\"), Text(\"b\"), Text(\"c\")]"
+        );
+    }
+
+    #[test]
+    fn keeps_a_fragment_only_at_the_root() {
+        let root = Html::<()>::Fragment(vec![Html::Fragment(vec![text("a")]), text("b")]);
+        assert_eq!(
+            format!("{:?}", root.resolve()),
+            "Fragment([Text(\"a\"), Text(\"b\")])"
+        );
+
+        assert_eq!(
+            label(Html::Fragment(vec![Html::Fragment(vec![text("a")])])),
+            "a"
+        );
+    }
+
+    #[test]
+    fn keeps_raw_html_through_maps() {
+        let raw = unsafe_raw_html::<i32>("<b>a</b>").map(|n| n.to_string());
+
+        assert_eq!(
+            format!("{:?}", raw.resolve()),
+            "UnsafeRawHtml(\"<b>a</b>\")"
+        );
+    }
+}
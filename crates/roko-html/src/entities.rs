@@ -0,0 +1,354 @@
+//! Named character references, e.g. `&copy;`. It has the ones that show up in handwritten markup:
+//! punctuation, symbols, arrows, math, Greek letters and Latin-1 accented letters.
+
+const ENTITIES: &[(&str, &str)] = &[
+    // Markup and ASCII punctuation.
+    ("amp", "&"),
+    ("lt", "<"),
+    ("gt", ">"),
+    ("quot", "\""),
+    ("apos", "'"),
+    ("Tab", "\t"),
+    ("NewLine", "\n"),
+    ("excl", "!"),
+    ("num", "#"),
+    ("dollar", "$"),
+    ("percnt", "%"),
+    ("lpar", "("),
+    ("rpar", ")"),
+    ("ast", "*"),
+    ("plus", "+"),
+    ("comma", ","),
+    ("period", "."),
+    ("sol", "/"),
+    ("colon", ":"),
+    ("semi", ";"),
+    ("equals", "="),
+    ("quest", "?"),
+    ("commat", "@"),
+    ("lsqb", "["),
+    ("bsol", "\\"),
+    ("rsqb", "]"),
+    ("Hat", "^"),
+    ("lowbar", "_"),
+    ("grave", "`"),
+    ("lcub", "{"),
+    ("verbar", "|"),
+    ("rcub", "}"),
+    // Spaces and typography.
+    ("nbsp", "\u{a0}"),
+    ("ensp", "\u{2002}"),
+    ("emsp", "\u{2003}"),
+    ("thinsp", "\u{2009}"),
+    ("zwnj", "\u{200c}"),
+    ("zwj", "\u{200d}"),
+    ("lrm", "\u{200e}"),
+    ("rlm", "\u{200f}"),
+    ("shy", "\u{ad}"),
+    ("ndash", "–"),
+    ("mdash", "—"),
+    ("lsquo", "‘"),
+    ("rsquo", "’"),
+    ("sbquo", "‚"),
+    ("ldquo", "“"),
+    ("rdquo", "”"),
+    ("bdquo", "„"),
+    ("laquo", "«"),
+    ("raquo", "»"),
+    ("lsaquo", "‹"),
+    ("rsaquo", "›"),
+    ("hellip", "…"),
+    ("bull", "•"),
+    ("middot", "·"),
+    ("dagger", "†"),
+    ("Dagger", "‡"),
+    ("permil", "‰"),
+    ("prime", "′"),
+    ("Prime", "″"),
+    ("oline", "‾"),
+    ("iexcl", "¡"),
+    ("iquest", "¿"),
+    ("para", "¶"),
+    ("sect", "§"),
+    // Symbols.
+    ("copy", "©"),
+    ("reg", "®"),
+    ("trade", "™"),
+    ("deg", "°"),
+    ("cent", "¢"),
+    ("pound", "£"),
+    ("curren", "¤"),
+    ("yen", "¥"),
+    ("euro", "€"),
+    ("brvbar", "¦"),
+    ("uml", "¨"),
+    ("ordf", "ª"),
+    ("ordm", "º"),
+    ("not", "¬"),
+    ("macr", "¯"),
+    ("acute", "´"),
+    ("micro", "µ"),
+    ("cedil", "¸"),
+    ("spades", "♠"),
+    ("clubs", "♣"),
+    ("hearts", "♥"),
+    ("diams", "♦"),
+    ("loz", "◊"),
+    ("check", "✓"),
+    ("cross", "✗"),
+    ("star", "☆"),
+    ("starf", "★"),
+    // Arrows.
+    ("larr", "←"),
+    ("uarr", "↑"),
+    ("rarr", "→"),
+    ("darr", "↓"),
+    ("harr", "↔"),
+    ("crarr", "↵"),
+    ("lArr", "⇐"),
+    ("uArr", "⇑"),
+    ("rArr", "⇒"),
+    ("dArr", "⇓"),
+    ("hArr", "⇔"),
+    // Math.
+    ("plusmn", "±"),
+    ("times", "×"),
+    ("divide", "÷"),
+    ("minus", "−"),
+    ("lowast", "∗"),
+    ("frasl", "⁄"),
+    ("sup1", "¹"),
+    ("sup2", "²"),
+    ("sup3", "³"),
+    ("frac14", "¼"),
+    ("frac12", "½"),
+    ("frac34", "¾"),
+    ("forall", "∀"),
+    ("part", "∂"),
+    ("exist", "∃"),
+    ("empty", "∅"),
+    ("nabla", "∇"),
+    ("isin", "∈"),
+    ("notin", "∉"),
+    ("ni", "∋"),
+    ("prod", "∏"),
+    ("sum", "∑"),
+    ("radic", "√"),
+    ("prop", "∝"),
+    ("infin", "∞"),
+    ("ang", "∠"),
+    ("and", "∧"),
+    ("or", "∨"),
+    ("cap", "∩"),
+    ("cup", "∪"),
+    ("int", "∫"),
+    ("there4", "∴"),
+    ("sim", "∼"),
+    ("cong", "≅"),
+    ("asymp", "≈"),
+    ("ne", "≠"),
+    ("equiv", "≡"),
+    ("le", "≤"),
+    ("ge", "≥"),
+    ("sub", "⊂"),
+    ("sup", "⊃"),
+    ("nsub", "⊄"),
+    ("sube", "⊆"),
+    ("supe", "⊇"),
+    ("oplus", "⊕"),
+    ("otimes", "⊗"),
+    ("perp", "⊥"),
+    ("sdot", "⋅"),
+    ("lceil", "⌈"),
+    ("rceil", "⌉"),
+    ("lfloor", "⌊"),
+    ("rfloor", "⌋"),
+    ("lang", "⟨"),
+    ("rang", "⟩"),
+    // Greek letters.
+    ("Alpha", "Α"),
+    ("Beta", "Β"),
+    ("Gamma", "Γ"),
+    ("Delta", "Δ"),
+    ("Epsilon", "Ε"),
+    ("Zeta", "Ζ"),
+    ("Eta", "Η"),
+    ("Theta", "Θ"),
+    ("Iota", "Ι"),
+    ("Kappa", "Κ"),
+    ("Lambda", "Λ"),
+    ("Mu", "Μ"),
+    ("Nu", "Ν"),
+    ("Xi", "Ξ"),
+    ("Omicron", "Ο"),
+    ("Pi", "Π"),
+    ("Rho", "Ρ"),
+    ("Sigma", "Σ"),
+    ("Tau", "Τ"),
+    ("Upsilon", "Υ"),
+    ("Phi", "Φ"),
+    ("Chi", "Χ"),
+    ("Psi", "Ψ"),
+    ("Omega", "Ω"),
+    ("alpha", "α"),
+    ("beta", "β"),
+    ("gamma", "γ"),
+    ("delta", "δ"),
+    ("epsilon", "ε"),
+    ("zeta", "ζ"),
+    ("eta", "η"),
+    ("theta", "θ"),
+    ("iota", "ι"),
+    ("kappa", "κ"),
+    ("lambda", "λ"),
+    ("mu", "μ"),
+    ("nu", "ν"),
+    ("xi", "ξ"),
+    ("omicron", "ο"),
+    ("pi", "π"),
+    ("rho", "ρ"),
+    ("sigmaf", "ς"),
+    ("sigma", "σ"),
+    ("tau", "τ"),
+    ("upsilon", "υ"),
+    ("phi", "φ"),
+    ("chi", "χ"),
+    ("psi", "ψ"),
+    ("omega", "ω"),
+    ("thetasym", "ϑ"),
+    ("piv", "ϖ"),
+    // Latin-1 letters.
+    ("Agrave", "À"),
+    ("Aacute", "Á"),
+    ("Acirc", "Â"),
+    ("Atilde", "Ã"),
+    ("Auml", "Ä"),
+    ("Aring", "Å"),
+    ("AElig", "Æ"),
+    ("Ccedil", "Ç"),
+    ("Egrave", "È"),
+    ("Eacute", "É"),
+    ("Ecirc", "Ê"),
+    ("Euml", "Ë"),
+    ("Igrave", "Ì"),
+    ("Iacute", "Í"),
+    ("Icirc", "Î"),
+    ("Iuml", "Ï"),
+    ("ETH", "Ð"),
+    ("Ntilde", "Ñ"),
+    ("Ograve", "Ò"),
+    ("Oacute", "Ó"),
+    ("Ocirc", "Ô"),
+    ("Otilde", "Õ"),
+    ("Ouml", "Ö"),
+    ("Oslash", "Ø"),
+    ("Ugrave", "Ù"),
+    ("Uacute", "Ú"),
+    ("Ucirc", "Û"),
+    ("Uuml", "Ü"),
+    ("Yacute", "Ý"),
+    ("THORN", "Þ"),
+    ("szlig", "ß"),
+    ("agrave", "à"),
+    ("aacute", "á"),
+    ("acirc", "â"),
+    ("atilde", "ã"),
+    ("auml", "ä"),
+    ("aring", "å"),
+    ("aelig", "æ"),
+    ("ccedil", "ç"),
+    ("egrave", "è"),
+    ("eacute", "é"),
+    ("ecirc", "ê"),
+    ("euml", "ë"),
+    ("igrave", "ì"),
+    ("iacute", "í"),
+    ("icirc", "î"),
+    ("iuml", "ï"),
+    ("eth", "ð"),
+    ("ntilde", "ñ"),
+    ("ograve", "ò"),
+    ("oacute", "ó"),
+    ("ocirc", "ô"),
+    ("otilde", "õ"),
+    ("ouml", "ö"),
+    ("oslash", "ø"),
+    ("ugrave", "ù"),
+    ("uacute", "ú"),
+    ("ucirc", "û"),
+    ("uuml", "ü"),
+    ("yacute", "ý"),
+    ("thorn", "þ"),
+    ("yuml", "ÿ"),
+    ("OElig", "Œ"),
+    ("oelig", "œ"),
+    ("Scaron", "Š"),
+    ("scaron", "š"),
+    ("Yuml", "Ÿ"),
+    ("fnof", "ƒ"),
+    ("circ", "ˆ"),
+    ("tilde", "˜"),
+];
+
+/// The text of a named reference without its `&` and `;`.
+pub(crate) fn named(name: &str) -> Option<&'static str> {
+    ENTITIES
+        .iter()
+        .find(|(entity, _)| *entity == name)
+        .map(|(_, text)| *text)
+}
+
+/// Decodes the character references of `text`, e.g. `&lt;`, `&#39;` or `&#x27;`. Unknown named
+/// references are kept as they are, like browsers do, so `&nope;` is text.
+pub(crate) fn decode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(at) = rest.find('&') {
+        out.push_str(&rest[..at]);
+        rest = &rest[at..];
+
+        // A reference is a name or a number up to a `;`, anything else is a literal `&`.
+        let reference = rest[1..]
+            .find(|ch: char| !ch.is_ascii_alphanumeric() && ch != '#')
+            .filter(|end| rest[1 + end..].starts_with(';'))
+            .map(|end| &rest[1..1 + end])
+            .filter(|reference| !reference.is_empty());
+
+        let Some(reference) = reference else {
+            out.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+
+        let decoded = match reference.strip_prefix('#') {
+            Some(number) => {
+                let (digits, radix) = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => (hex, 16),
+                    None => (number, 10),
+                };
+
+                // `&#0;`, surrogates and numbers out of range are replaced like browsers do.
+                (!digits.is_empty() && digits.chars().all(|ch| ch.is_digit(radix))).then(|| {
+                    let ch = u32::from_str_radix(digits, radix)
+                        .ok()
+                        .filter(|&code| code != 0)
+                        .and_then(char::from_u32);
+                    out.push(ch.unwrap_or(char::REPLACEMENT_CHARACTER))
+                })
+            }
+            None => named(reference).map(|text| out.push_str(text)),
+        };
+
+        if decoded.is_none() {
+            out.push('&');
+            out.push_str(reference);
+            out.push(';');
+        }
+
+        rest = &rest[reference.len() + 2..];
+    }
+
+    out.push_str(rest);
+    out
+}
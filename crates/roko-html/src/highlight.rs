@@ -0,0 +1,577 @@
+//! Syntax highlighting of code into `<span>`s with a class for each kind of token, e.g.
+//! `<span class="hl-keyword">fn</span>`. It's plain Rust, so the markdown renderer highlights code
+//! blocks in the same way when compiling posts and when rendering fetched content, and the page
+//! doesn't need a JS highlighter. [THEME] colours the classes.
+//!
+//! Languages are described by a [Grammar] of their lexical rules, which is enough to tell
+//! keywords, types, strings and comments apart without parsing the code.
+
+use crate::{Attribute, Children, Html};
+
+/// Stylesheet with colours for every [Token], for code on a dark background. `markdown!` adds it
+/// to the bundled stylesheet, markdown rendered at runtime needs it in the page.
+pub const THEME: &str = include_str!("../theme/highlight.css");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    Keyword,
+    /// Capitalised names and primitive types, which include constructors like `Some`.
+    Type,
+    /// Literals like `true` or `null`.
+    Constant,
+    /// Names that are called, e.g. the `push` of `items.push(item)`.
+    Function,
+    Macro,
+    String,
+    Number,
+    Comment,
+    /// Attributes like `#[derive(Debug)]` in Rust.
+    Attribute,
+    Lifetime,
+    Operator,
+}
+
+impl Token {
+    pub fn class(self) -> &'static str {
+        match self {
+            Token::Keyword => "hl-keyword",
+            Token::Type => "hl-type",
+            Token::Constant => "hl-constant",
+            Token::Function => "hl-function",
+            Token::Macro => "hl-macro",
+            Token::String => "hl-string",
+            Token::Number => "hl-number",
+            Token::Comment => "hl-comment",
+            Token::Attribute => "hl-attribute",
+            Token::Lifetime => "hl-lifetime",
+            Token::Operator => "hl-operator",
+        }
+    }
+}
+
+/// Lexical rules of a language.
+#[derive(Debug)]
+pub struct Grammar {
+    /// Names of the language in the info string of a code block, e.g. `rust` and `rs`.
+    pub names: &'static [&'static str],
+    keywords: &'static [&'static str],
+    types: &'static [&'static str],
+    constants: &'static [&'static str],
+    line_comment: &'static str,
+    block_comment: (&'static str, &'static str),
+    /// Block comments can be inside of other ones, like in Rust and Haskell.
+    nested_comments: bool,
+    /// Characters that start and end a string.
+    quotes: &'static [char],
+    operators: &'static str,
+    /// `'a'` is a character and `'a` a lifetime, like in Rust.
+    lifetimes: bool,
+    /// `'` is part of names, like the `x'` of Haskell.
+    primes: bool,
+    /// `r#"..."#` and `b"..."` are strings, like in Rust.
+    prefixed_strings: bool,
+    /// Names followed by `(` are functions. Haskell applies them without parentheses.
+    calls: bool,
+    /// Names followed by `!` are macros, and `#[...]` are attributes.
+    macros: bool,
+}
+
+pub const RUST: Grammar = Grammar {
+    names: &["rust", "rs"],
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "type",
+        "unsafe", "use", "where", "while", "yield",
+    ],
+    types: &[
+        "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32",
+        "i64", "i128", "isize", "f32", "f64",
+    ],
+    constants: &["true", "false"],
+    line_comment: "//",
+    block_comment: ("/*", "*/"),
+    nested_comments: true,
+    quotes: &['"'],
+    operators: "+-*/%=<>!&|^?:",
+    lifetimes: true,
+    primes: false,
+    prefixed_strings: true,
+    calls: true,
+    macros: true,
+};
+
+pub const HASKELL: Grammar = Grammar {
+    names: &["haskell", "hs"],
+    keywords: &[
+        "as",
+        "case",
+        "class",
+        "data",
+        "default",
+        "deriving",
+        "do",
+        "else",
+        "family",
+        "forall",
+        "foreign",
+        "hiding",
+        "if",
+        "import",
+        "in",
+        "infix",
+        "infixl",
+        "infixr",
+        "instance",
+        "let",
+        "mdo",
+        "module",
+        "newtype",
+        "of",
+        "qualified",
+        "rec",
+        "then",
+        "type",
+        "where",
+    ],
+    types: &[],
+    constants: &[],
+    line_comment: "--",
+    block_comment: ("{-", "-}"),
+    nested_comments: true,
+    quotes: &['"'],
+    operators: "+-*/%=<>!&|^?:.$@~\\#",
+    lifetimes: false,
+    primes: true,
+    prefixed_strings: false,
+    calls: false,
+    macros: false,
+};
+
+pub const JAVASCRIPT: Grammar = Grammar {
+    names: &["javascript", "js", "jsx", "mjs"],
+    keywords: &[
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "debugger",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "export",
+        "extends",
+        "finally",
+        "for",
+        "from",
+        "function",
+        "if",
+        "import",
+        "in",
+        "instanceof",
+        "let",
+        "new",
+        "of",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "try",
+        "typeof",
+        "var",
+        "void",
+        "while",
+        "with",
+        "yield",
+    ],
+    types: &[],
+    constants: &["true", "false", "null", "undefined", "NaN", "Infinity"],
+    line_comment: "//",
+    block_comment: ("/*", "*/"),
+    nested_comments: false,
+    quotes: &['"', '\'', '`'],
+    operators: "+-*/%=<>!&|^?:~",
+    lifetimes: false,
+    primes: false,
+    prefixed_strings: false,
+    calls: true,
+    macros: false,
+};
+
+const GRAMMARS: &[&Grammar] = &[&RUST, &HASKELL, &JAVASCRIPT];
+
+/// Grammar of a language by one of its names, ignoring case.
+pub fn grammar(language: &str) -> Option<&'static Grammar> {
+    GRAMMARS.iter().copied().find(|grammar| {
+        grammar
+            .names
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(language))
+    })
+}
+
+/// Length of a string that starts at `quote`, with `\` escapes.
+fn string(code: &str, quote: char) -> usize {
+    let mut escaped = false;
+
+    for (at, ch) in code.char_indices().skip(1) {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ch if ch == quote => return at + ch.len_utf8(),
+            _ => {}
+        }
+    }
+
+    code.len()
+}
+
+/// Length of a Rust raw string like `r#"..."#`, if `code` starts with one.
+fn raw_string(code: &str) -> Option<usize> {
+    let rest = code.strip_prefix('b').unwrap_or(code).strip_prefix('r')?;
+    let hashes = rest.len() - rest.trim_start_matches('#').len();
+    rest[hashes..].strip_prefix('"')?;
+
+    let end = format!("\"{}", "#".repeat(hashes));
+    let start = code.len() - rest.len() + hashes + 1;
+
+    Some(
+        code[start..]
+            .find(&end)
+            .map_or(code.len(), |at| start + at + end.len()),
+    )
+}
+
+/// Length of a block comment.
+fn block_comment(code: &str, (open, close): (&str, &str), nested: bool) -> usize {
+    let mut depth = 0;
+    let mut at = 0;
+
+    while at < code.len() {
+        if code[at..].starts_with(open) && (nested || depth == 0) {
+            depth += 1;
+            at += open.len();
+        } else if code[at..].starts_with(close) {
+            depth -= 1;
+            at += close.len();
+
+            if depth == 0 {
+                return at;
+            }
+        } else {
+            at += code[at..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    code.len()
+}
+
+/// Length of a number, e.g. `0xff`, `1_000` or `2.5e10`. A `.` is only part of it when a digit
+/// follows, so `0..10` is a range.
+fn number(code: &str) -> usize {
+    let mut len = 0;
+
+    for (at, ch) in code.char_indices() {
+        let fraction = ch == '.' && code[at + 1..].starts_with(|ch: char| ch.is_ascii_digit());
+
+        if !(ch.is_ascii_alphanumeric() || ch == '_' || fraction) {
+            break;
+        }
+
+        len = at + 1;
+    }
+
+    len
+}
+
+impl Grammar {
+    fn name(&self, code: &str) -> usize {
+        code.find(|ch: char| !(ch.is_alphanumeric() || ch == '_' || (ch == '\'' && self.primes)))
+            .unwrap_or(code.len())
+    }
+
+    /// Length of a Rust attribute, up to its matching `]`.
+    fn attribute(&self, code: &str) -> Option<usize> {
+        if !self.macros || !(code.starts_with("#[") || code.starts_with("#![")) {
+            return None;
+        }
+
+        let mut depth = 0;
+
+        for (at, ch) in code.char_indices() {
+            match ch {
+                '[' => depth += 1,
+                ']' if depth == 1 => return Some(at + 1),
+                ']' => depth -= 1,
+                _ => {}
+            }
+        }
+
+        Some(code.len())
+    }
+
+    /// Length of a Rust character literal or lifetime that starts with `'`.
+    fn quote(&self, code: &str) -> (Token, usize) {
+        let rest = &code[1..];
+
+        // An escape or a character followed by `'` is a character, `'a` alone is a lifetime.
+        let char_len = match rest.strip_prefix('\\') {
+            Some(_) => rest
+                .get(2..)
+                .and_then(|escape| escape.find('\''))
+                .map(|at| at + 2),
+            None => rest
+                .chars()
+                .next()
+                .filter(|ch| rest[ch.len_utf8()..].starts_with('\''))
+                .map(|ch| ch.len_utf8()),
+        };
+
+        match char_len {
+            Some(len) => (Token::String, len + 2),
+            None => (Token::Lifetime, 1 + self.name(rest)),
+        }
+    }
+
+    /// Kind of a name, which depends on what follows it.
+    fn classify(&self, name: &str, after: &str) -> Option<Token> {
+        if self.keywords.contains(&name) {
+            Some(Token::Keyword)
+        } else if self.constants.contains(&name) {
+            Some(Token::Constant)
+        } else if self.types.contains(&name) || name.starts_with(char::is_uppercase) {
+            Some(Token::Type)
+        } else if self.macros && after.starts_with('!') && !after.starts_with("!=") {
+            Some(Token::Macro)
+        } else if self.calls && after.trim_start().starts_with('(') {
+            Some(Token::Function)
+        } else {
+            None
+        }
+    }
+
+    /// Splits `code` into tokens, the text between them has no token.
+    pub fn tokens<'a>(&self, code: &'a str) -> Vec<(Option<Token>, &'a str)> {
+        let mut tokens: Vec<(Option<Token>, &'a str)> = Vec::new();
+        let mut at = 0;
+
+        while at < code.len() {
+            let rest = &code[at..];
+            let ch = rest.chars().next().expect("the rest isn't empty");
+
+            let (token, len) = if rest.starts_with(self.line_comment) {
+                (Some(Token::Comment), rest.find('\n').unwrap_or(rest.len()))
+            } else if rest.starts_with(self.block_comment.0) {
+                (
+                    Some(Token::Comment),
+                    block_comment(rest, self.block_comment, self.nested_comments),
+                )
+            } else if let Some(len) = self.attribute(rest) {
+                (Some(Token::Attribute), len)
+            } else if let Some(len) = raw_string(rest).filter(|_| self.prefixed_strings) {
+                (Some(Token::String), len)
+            } else if self.quotes.contains(&ch) {
+                (Some(Token::String), string(rest, ch))
+            } else if ch == '\'' && self.lifetimes {
+                let (token, len) = self.quote(rest);
+                (Some(token), len)
+            } else if ch == '\'' {
+                // A Haskell character, quotes inside of names were taken with them.
+                (Some(Token::String), string(rest, '\''))
+            } else if ch.is_ascii_digit() {
+                (Some(Token::Number), number(rest))
+            } else if ch.is_alphabetic() || ch == '_' {
+                let len = self.name(rest);
+                let name = &rest[..len];
+                let after = &rest[len..];
+
+                // `b"bytes"` in Rust is a string with a prefix.
+                match after.chars().next() {
+                    Some('"') if self.prefixed_strings && name == "b" => {
+                        (Some(Token::String), len + string(after, '"'))
+                    }
+                    // The `!` of a macro is part of its name.
+                    _ => match self.classify(name, after) {
+                        Some(Token::Macro) => (Some(Token::Macro), len + 1),
+                        token => (token, len),
+                    },
+                }
+            } else if self.operators.contains(ch) {
+                let len = rest
+                    .find(|ch| !self.operators.contains(ch))
+                    .unwrap_or(rest.len());
+                (Some(Token::Operator), len)
+            } else {
+                (None, ch.len_utf8())
+            };
+
+            let text = &code[at..at + len];
+            at += len;
+
+            match (tokens.last_mut(), token) {
+                (Some((None, last)), None) => {
+                    *last = &code[at - len - last.len()..at];
+                }
+                _ => tokens.push((token, text)),
+            }
+        }
+
+        tokens
+    }
+
+    /// Highlights `code` into text and `<span>` nodes.
+    pub fn highlight<Msg>(&self, code: &str) -> Children<Msg> {
+        self.tokens(code)
+            .into_iter()
+            .map(|(token, text)| match token {
+                Some(token) => Html::node(
+                    "span",
+                    None,
+                    vec![Attribute::Class(vec![token.class().to_string()])],
+                    vec![Html::Text(text.to_string())],
+                ),
+                None => Html::Text(text.to_string()),
+            })
+            .collect()
+    }
+}
+
+/// Highlights `code` of a language by one of its names, if there's a grammar for it.
+///
+/// ```ignore
+/// html! { <pre><code>{highlight::highlight("rust", code).unwrap_or_else(|| vec![text(code)])}</code></pre> }
+/// ```
+pub fn highlight<Msg>(language: &str, code: &str) -> Option<Children<Msg>> {
+    grammar(language).map(|grammar| grammar.highlight(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Token::*;
+
+    /// Tokens of `code`, without the text between them.
+    fn tokens<'a>(grammar: &Grammar, code: &'a str) -> Vec<(Token, &'a str)> {
+        grammar
+            .tokens(code)
+            .into_iter()
+            .filter_map(|(token, text)| Some((token?, text)))
+            .collect()
+    }
+
+    #[test]
+    fn tokenizes_rust_raw_strings() {
+        assert_eq!(
+            tokens(&RUST, r###"r#"a "b" c"# br"d" b"e\"" r##"f"#g"##"###),
+            [
+                (String, r###"r#"a "b" c"#"###),
+                (String, r#"br"d""#),
+                (String, r#"b"e\"""#),
+                (String, r###"r##"f"#g"##"###),
+            ]
+        );
+    }
+
+    #[test]
+    fn tells_rust_chars_from_lifetimes() {
+        assert_eq!(
+            tokens(&RUST, r"'a' 'a '\n' '\u{1F600}' '' &'static"),
+            [
+                (String, "'a'"),
+                (Lifetime, "'a"),
+                (String, r"'\n'"),
+                (String, r"'\u{1F600}'"),
+                (Lifetime, "'"),
+                (Lifetime, "'"),
+                (Operator, "&"),
+                (Lifetime, "'static"),
+            ]
+        );
+    }
+
+    #[test]
+    fn nests_comments_where_the_language_does() {
+        let code = "/* a /* b */ c */ d";
+
+        assert_eq!(tokens(&RUST, code), [(Comment, "/* a /* b */ c */")]);
+        assert_eq!(
+            tokens(&JAVASCRIPT, code),
+            [(Comment, "/* a /* b */"), (Operator, "*/")]
+        );
+        assert_eq!(
+            tokens(&HASKELL, "{- a {- b -} c -} d -- e"),
+            [(Comment, "{- a {- b -} c -}"), (Comment, "-- e")]
+        );
+    }
+
+    #[test]
+    fn keeps_ranges_out_of_numbers() {
+        assert_eq!(
+            tokens(&RUST, "0..10 2.5e10 0xff_u8"),
+            [
+                (Number, "0"),
+                (Number, "10"),
+                (Number, "2.5e10"),
+                (Number, "0xff_u8"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_haskell_primes_in_names() {
+        assert_eq!(
+            HASKELL.tokens("let x' = foldl' f 'a'"),
+            [
+                (Some(Keyword), "let"),
+                (None, " x' "),
+                (Some(Operator), "="),
+                (None, " foldl' f "),
+                (Some(String), "'a'"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tells_macros_from_inequality() {
+        assert_eq!(
+            tokens(&RUST, r#"println!("{}", a != b); x!=y"#),
+            [
+                (Macro, "println!"),
+                (String, r#""{}""#),
+                (Operator, "!="),
+                (Operator, "!="),
+            ]
+        );
+    }
+
+    #[test]
+    fn never_panics_and_keeps_the_text() {
+        let pieces = [
+            "'", "\"", "\\", "r", "#", "b", "/", "*", "{", "-", "}", ".", "0", "é", "!", "[", "]",
+            "(", "a", "\n", "`",
+        ];
+
+        for grammar in GRAMMARS {
+            for a in pieces {
+                for b in pieces {
+                    for c in pieces {
+                        let code = format!("{a}{b}{c}");
+                        let text = grammar
+                            .tokens(&code)
+                            .into_iter()
+                            .map(|(_, text)| text)
+                            .collect::<std::string::String>();
+                        assert_eq!(text, code, "{:?}", grammar.names);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,28 +1,246 @@
 //! Module to data structures related to HTML.
 
+pub mod context;
+mod entities;
+pub mod highlight;
+pub mod map;
+pub mod markdown;
+mod parser;
+pub mod props;
+pub mod sanitize;
+pub mod spec;
+
 use std::{collections::HashMap, fmt::Debug, sync::Arc};
 
+use context::{Consumer, Provider};
+use map::{Identity, Map};
+pub use parser::{parse, ParseError};
+pub use sanitize::Sanitizer;
+
 pub type Attrs<Message> = Vec<Attribute<Message>>;
 
 pub type Children<Message> = Vec<Html<Message>>;
 
+/// Function that rewrites every event handler of a tree, used to change its message type.
+pub(crate) type HandlerMap<Msg, New> = Arc<dyn Fn(Handler<Msg>) -> Handler<New>>;
+
+/// What an event attribute does when it fires. It either sends a message or runs a callback that
+/// may compute one. Callbacks can't be compared, so two of them are only equal if they are the
+/// same closure.
+pub enum Handler<Msg> {
+    Message(Arc<Msg>),
+    Callback(Arc<dyn Fn() -> Option<Msg>>),
+    /// Handler of a view inside of [Html::map]. Its callback is built on every resolve, so it's
+    /// compared by the handler it comes from and the maps around it instead.
+    Mapped(Arc<dyn Fn() -> Option<Msg>>, Identity),
+}
+
+impl<Msg> Handler<Msg> {
+    pub fn message(msg: Msg) -> Self {
+        Self::Message(Arc::new(msg))
+    }
+
+    /// Returns the message that has to be sent to the application, if any.
+    pub fn fire(&self) -> Option<Arc<Msg>> {
+        match self {
+            Self::Message(msg) => Some(msg.clone()),
+            Self::Callback(callback) | Self::Mapped(callback, _) => callback().map(Arc::new),
+        }
+    }
+}
+
+impl<Msg: Clone + 'static> Handler<Msg> {
+    fn map<New: 'static>(self, f: &Arc<dyn Fn(Msg) -> New>) -> Handler<New> {
+        match self {
+            Self::Message(msg) => Handler::Message(Arc::new(f(
+                Arc::try_unwrap(msg).unwrap_or_else(|msg| (*msg).clone())
+            ))),
+            Self::Callback(callback) | Self::Mapped(callback, _) => {
+                let f = f.clone();
+                Handler::Callback(Arc::new(move || callback().map(&*f)))
+            }
+        }
+    }
+}
+
+impl<Msg: PartialEq> PartialEq for Handler<Msg> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Message(l), Self::Message(r)) => l == r,
+            (Self::Callback(l), Self::Callback(r)) => Arc::ptr_eq(l, r),
+            (Self::Mapped(_, l), Self::Mapped(_, r)) => l == r,
+            _ => false,
+        }
+    }
+}
+
+impl<Msg: Eq> Eq for Handler<Msg> {}
+
+impl<Msg> Clone for Handler<Msg> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Message(arg0) => Self::Message(arg0.clone()),
+            Self::Callback(arg0) => Self::Callback(arg0.clone()),
+            Self::Mapped(arg0, arg1) => Self::Mapped(arg0.clone(), arg1.clone()),
+        }
+    }
+}
+
+/// State of the element that fired an input or change event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputEvent {
+    pub value: String,
+    pub checked: bool,
+}
+
+/// Handler of an event that reads the element that fired it, like the text of an input. Like
+/// callbacks, two listeners are only equal if they are the same closure.
+pub struct Listener<Msg>(Arc<dyn Fn(InputEvent) -> Option<Arc<Msg>>>);
+
+impl<Msg: 'static> Listener<Msg> {
+    pub fn new(f: impl Fn(InputEvent) -> Option<Msg> + 'static) -> Self {
+        Self(Arc::new(move |event| f(event).map(Arc::new)))
+    }
+
+    /// Returns the message that has to be sent to the application, if any.
+    pub fn fire(&self, event: InputEvent) -> Option<Arc<Msg>> {
+        (self.0)(event)
+    }
+
+    fn map_handlers<New: 'static>(self, f: &HandlerMap<Msg, New>) -> Listener<New> {
+        let f = f.clone();
+        Listener(Arc::new(move |event| {
+            self.fire(event)
+                .and_then(|msg| f(Handler::Message(msg)).fire())
+        }))
+    }
+}
+
+impl<Msg> PartialEq for Listener<Msg> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<Msg> Eq for Listener<Msg> {}
+
+impl<Msg> Clone for Listener<Msg> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
 /// Html attribute in a format that supports a tag without a value e.g `disabled` and with
 /// a value e.g `value="Hello World"`.
 #[derive(PartialEq, Eq)]
 pub enum Attribute<Msg> {
-    OnClick(Arc<Msg>),
-    OnMount(Arc<Msg>),
-    OnUnmount(Arc<Msg>),
+    OnClick(Handler<Msg>),
+    OnMount(Handler<Msg>),
+    OnUnmount(Handler<Msg>),
+    OnInput(Listener<Msg>),
+    OnChange(Listener<Msg>),
     Custom(String, String),
+    /// Boolean attribute like `disabled`, which is present on the element only while it's `true`.
+    Bool(String, bool),
+    /// Class list, set with `classList` so only the classes that changed are touched.
+    Class(Vec<String>),
+    /// Inline style declarations, set one property at a time.
+    Style(Vec<(String, String)>),
+    /// JS property of the element like `value` or `checked`. The user can change them, so they
+    /// are compared with the live value of the element on every patch instead of the last view.
+    Property(String, PropertyValue),
+}
+
+/// Value of an [Attribute::Property].
+#[derive(Debug, Clone)]
+pub enum PropertyValue {
+    String(String),
+    Bool(bool),
+    Number(f64),
+}
+
+impl PartialEq for PropertyValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::String(l), Self::String(r)) => l == r,
+            (Self::Bool(l), Self::Bool(r)) => l == r,
+            (Self::Number(l), Self::Number(r)) => l.to_bits() == r.to_bits(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for PropertyValue {}
+
+impl From<String> for PropertyValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for PropertyValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<bool> for PropertyValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<f64> for PropertyValue {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
 }
 
+/// Numbers that fit in a `f64` without losing precision.
+macro_rules! number_from {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for PropertyValue {
+                fn from(value: $ty) -> Self {
+                    Self::Number(value.into())
+                }
+            }
+        )*
+    };
+}
+
+number_from!(i8, i16, i32, u8, u16, u32, f32);
+
+/// Numbers that are rounded to the closest `f64` like JS does.
+macro_rules! number_as {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for PropertyValue {
+                fn from(value: $ty) -> Self {
+                    Self::Number(value as f64)
+                }
+            }
+        )*
+    };
+}
+
+number_as!(i64, u64, isize, usize);
+
 impl<Msg> Debug for Attribute<Msg> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::OnClick(_) => f.debug_tuple("OnClick").finish(),
             Self::OnMount(_) => f.debug_tuple("OnMount").finish(),
             Self::OnUnmount(_) => f.debug_tuple("OnUnmount").finish(),
+            Self::OnInput(_) => f.debug_tuple("OnInput").finish(),
+            Self::OnChange(_) => f.debug_tuple("OnChange").finish(),
             Self::Custom(arg0, arg1) => f.debug_tuple("Custom").field(arg0).field(arg1).finish(),
+            Self::Bool(arg0, arg1) => f.debug_tuple("Bool").field(arg0).field(arg1).finish(),
+            Self::Class(arg0) => f.debug_tuple("Class").field(arg0).finish(),
+            Self::Style(arg0) => f.debug_tuple("Style").field(arg0).finish(),
+            Self::Property(arg0, arg1) => {
+                f.debug_tuple("Property").field(arg0).field(arg1).finish()
+            }
         }
     }
 }
@@ -33,26 +251,231 @@ impl<Msg> Clone for Attribute<Msg> {
             Self::OnClick(arg0) => Self::OnClick(arg0.clone()),
             Self::OnMount(arg0) => Self::OnMount(arg0.clone()),
             Self::OnUnmount(arg0) => Self::OnUnmount(arg0.clone()),
+            Self::OnInput(arg0) => Self::OnInput(arg0.clone()),
+            Self::OnChange(arg0) => Self::OnChange(arg0.clone()),
             Self::Custom(arg0, arg1) => Self::Custom(arg0.clone(), arg1.clone()),
+            Self::Bool(arg0, arg1) => Self::Bool(arg0.clone(), *arg1),
+            Self::Class(arg0) => Self::Class(arg0.clone()),
+            Self::Style(arg0) => Self::Style(arg0.clone()),
+            Self::Property(arg0, arg1) => Self::Property(arg0.clone(), arg1.clone()),
+        }
+    }
+}
+
+impl<Msg> Attribute<Msg> {
+    /// What the attribute sets on the element. Attributes with the same key replace each other.
+    pub fn key(&self) -> String {
+        match self {
+            Self::OnClick(_) => "onclick".to_string(),
+            Self::OnMount(_) => "onmount".to_string(),
+            Self::OnUnmount(_) => "onunmount".to_string(),
+            Self::OnInput(_) => "oninput".to_string(),
+            Self::OnChange(_) => "onchange".to_string(),
+            Self::Custom(name, _) | Self::Bool(name, _) => name.clone(),
+            Self::Class(_) => "class".to_string(),
+            Self::Style(_) => "style".to_string(),
+            Self::Property(name, _) => format!("prop:{name}"),
+        }
+    }
+}
+
+impl<Msg: Clone + 'static> Attribute<Msg> {
+    /// Changes the message type of the attribute by applying `f` to the message of the event
+    /// attributes. Messages are converted right away so the result can still be compared with
+    /// the previous render.
+    pub fn map<New: 'static>(self, f: impl Fn(Msg) -> New + 'static) -> Attribute<New> {
+        let f: Arc<dyn Fn(Msg) -> New> = Arc::new(f);
+        self.map_handlers(
+            &(Arc::new(move |handler: Handler<Msg>| handler.map(&f)) as HandlerMap<_, _>),
+        )
+    }
+
+    fn map_handlers<New: 'static>(self, f: &HandlerMap<Msg, New>) -> Attribute<New> {
+        match self {
+            Self::OnClick(handler) => Attribute::OnClick(f(handler)),
+            Self::OnMount(handler) => Attribute::OnMount(f(handler)),
+            Self::OnUnmount(handler) => Attribute::OnUnmount(f(handler)),
+            Self::OnInput(listener) => Attribute::OnInput(listener.map_handlers(f)),
+            Self::OnChange(listener) => Attribute::OnChange(listener.map_handlers(f)),
+            Self::Custom(name, value) => Attribute::Custom(name, value),
+            Self::Bool(name, value) => Attribute::Bool(name, value),
+            Self::Class(classes) => Attribute::Class(classes),
+            Self::Style(declarations) => Attribute::Style(declarations),
+            Self::Property(name, value) => Attribute::Property(name, value),
         }
     }
 }
 
+/// Value of the `key` attribute, which `html!` gives to element functions. Most of them take an
+/// `Option<String>`, and the ones that can't work without a key, like `Component::mount`, take a
+/// [Key], so leaving the attribute out is a compile error.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Key(pub String);
+
+/// What `html!` gives to element functions for an element without a `key` attribute.
+#[derive(Debug, Clone, Copy)]
+pub struct NoKey;
+
+/// Conversion of a [Key] or [NoKey] into the key parameter of an element function.
+#[diagnostic::on_unimplemented(
+    message = "this element has to be mounted with a `key`",
+    label = "add a `key` attribute to it"
+)]
+pub trait IntoKey<T> {
+    fn into_key(self) -> T;
+}
+
+impl IntoKey<Option<String>> for Key {
+    fn into_key(self) -> Option<String> {
+        Some(self.0)
+    }
+}
+
+impl IntoKey<Option<String>> for NoKey {
+    fn into_key(self) -> Option<String> {
+        None
+    }
+}
+
+/// Lets element functions take a [Key] or a [NoKey] as they are.
+impl<T> IntoKey<T> for T {
+    fn into_key(self) -> T {
+        self
+    }
+}
+
+/// Values of the attributes as they would be written in the markup, e.g. for the props of a
+/// component. Booleans that are `false` and event attributes are left out.
 pub fn to_map<Msg>(attrs: Attrs<Msg>) -> HashMap<String, String> {
     let mut map = HashMap::new();
 
+    for attr in merge_attributes(attrs) {
+        let (key, value) = match attr {
+            Attribute::Custom(key, value) => (key, value),
+            Attribute::Bool(key, true) => (key, String::new()),
+            Attribute::Class(classes) => ("class".to_string(), classes.join(" ")),
+            Attribute::Style(declarations) => (
+                "style".to_string(),
+                declarations
+                    .iter()
+                    .map(|(property, value)| format!("{property}: {value};"))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            Attribute::Property(key, PropertyValue::String(value)) => (key, value),
+            Attribute::Property(key, PropertyValue::Number(value)) => (key, value.to_string()),
+            Attribute::Property(key, PropertyValue::Bool(true)) => (key, String::new()),
+            _ => continue,
+        };
+
+        map.insert(key, value);
+    }
+
+    map
+}
+
+/// Merges the attributes that set the same thing, which `html!` does for elements with
+/// `{..attrs}`. Classes and style declarations are concatenated, for everything else the last
+/// attribute wins. Merged attributes keep the position of the first one, so the list has the same
+/// order on every render.
+pub fn merge_attributes<Msg>(attrs: Attrs<Msg>) -> Attrs<Msg> {
+    let mut merged: Attrs<Msg> = Vec::with_capacity(attrs.len());
+    let mut positions = HashMap::new();
+
     for attr in attrs {
-        if let Attribute::Custom(key, value) = attr {
-            map.insert(key, value);
+        let attr = match attr {
+            Attribute::Custom(name, value) if name == "class" => {
+                Attribute::Class(class_names(&value).collect())
+            }
+            Attribute::Custom(name, value) if name == "style" => {
+                Attribute::Style(style_declarations(&value))
+            }
+            attr => attr,
+        };
+
+        let key = attr.key();
+
+        let Some(&at) = positions.get(&key) else {
+            positions.insert(key, merged.len());
+            merged.push(attr);
+            continue;
+        };
+
+        match (&mut merged[at], attr) {
+            (Attribute::Class(classes), Attribute::Class(more)) => classes.extend(more),
+            (Attribute::Style(declarations), Attribute::Style(more)) => declarations.extend(more),
+            (slot, attr) => *slot = attr,
         }
     }
 
-    map
+    merged
+}
+
+/// Splits the value of a `class` attribute into class names.
+pub fn class_names(class: &str) -> impl Iterator<Item = String> + '_ {
+    class.split_whitespace().map(ToString::to_string)
+}
+
+/// Splits the value of a `style` attribute into its declarations, e.g. `color: red; width: 2px`
+/// gives `[("color", "red"), ("width", "2px")]`.
+pub fn style_declarations(style: &str) -> Vec<(String, String)> {
+    style
+        .split(';')
+        .filter_map(|declaration| declaration.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .filter(|(name, _)| !name.is_empty())
+        .collect()
+}
+
+/// Joins style declarations back into the value of a `style` attribute.
+pub fn style_string(declarations: &[(String, String)]) -> String {
+    declarations
+        .iter()
+        .map(|(name, value)| format!("{name}: {value};"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Namespace of an element. Browsers only draw SVG and MathML elements that were created in their
+/// namespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Namespace {
+    #[default]
+    Html,
+    Svg,
+    MathMl,
+}
+
+impl Namespace {
+    pub fn uri(self) -> &'static str {
+        match self {
+            Namespace::Html => "http://www.w3.org/1999/xhtml",
+            Namespace::Svg => "http://www.w3.org/2000/svg",
+            Namespace::MathMl => "http://www.w3.org/1998/Math/MathML",
+        }
+    }
+
+    pub fn from_uri(uri: &str) -> Option<Self> {
+        [Namespace::Html, Namespace::Svg, Namespace::MathMl]
+            .into_iter()
+            .find(|namespace| namespace.uri() == uri)
+    }
+}
+
+/// Namespace of prefixed attributes like `xlink:href`, which have to be set with it.
+pub fn attribute_namespace(name: &str) -> Option<&'static str> {
+    match name.split_once(':')?.0 {
+        "xlink" => Some("http://www.w3.org/1999/xlink"),
+        "xml" => Some("http://www.w3.org/XML/1998/namespace"),
+        "xmlns" => Some("http://www.w3.org/2000/xmlns/"),
+        _ => None,
+    }
 }
 
 /// Html node that contains a tag, attributes and children.
 pub struct Node<Msg> {
     pub tag: &'static str,
+    pub namespace: Namespace,
     pub id: Option<String>,
     pub attributes: Vec<Attribute<Msg>>,
     pub children: Vec<Html<Msg>>,
@@ -62,6 +485,7 @@ impl<Msg> Debug for Node<Msg> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Node")
             .field("tag", &self.tag)
+            .field("namespace", &self.namespace)
             .field("id", &self.id)
             .field("attributes", &self.attributes)
             .field("children", &self.children)
@@ -73,6 +497,7 @@ impl<Msg> Clone for Node<Msg> {
     fn clone(&self) -> Self {
         Self {
             tag: self.tag,
+            namespace: self.namespace,
             id: self.id.clone(),
             attributes: self.attributes.clone(),
             children: self.children.clone(),
@@ -81,10 +506,18 @@ impl<Msg> Clone for Node<Msg> {
 }
 
 /// Html data structure that can be either a node or a text. This is the main data structure that
-/// is used to build a virtual dom.
+/// is used to build a virtual dom. A fragment groups nodes without a wrapper element, its children
+/// are placed directly in the parent.
 pub enum Html<Msg> {
     Node(Node<Msg>),
     Text(String),
+    /// Markup that is inserted as is, see [unsafe_raw_html].
+    UnsafeRawHtml(String),
+    Fragment(Vec<Html<Msg>>),
+    Provider(Provider<Msg>),
+    Consumer(Consumer<Msg>),
+    /// View of another message type, see [Html::map].
+    Map(Map<Msg>),
 }
 
 impl<Msg> Debug for Html<Msg> {
@@ -92,6 +525,11 @@ impl<Msg> Debug for Html<Msg> {
         match self {
             Self::Node(arg0) => f.debug_tuple("Node").field(arg0).finish(),
             Self::Text(arg0) => f.debug_tuple("Text").field(arg0).finish(),
+            Self::UnsafeRawHtml(arg0) => f.debug_tuple("UnsafeRawHtml").field(arg0).finish(),
+            Self::Fragment(arg0) => f.debug_tuple("Fragment").field(arg0).finish(),
+            Self::Provider(arg0) => f.debug_tuple("Provider").field(arg0).finish(),
+            Self::Consumer(arg0) => f.debug_tuple("Consumer").field(arg0).finish(),
+            Self::Map(arg0) => f.debug_tuple("Map").field(arg0).finish(),
         }
     }
 }
@@ -102,9 +540,21 @@ impl<Msg> Html<Msg> {
         id: Option<String>,
         attributes: Vec<Attribute<Msg>>,
         children: Vec<Html<Msg>>,
+    ) -> Self {
+        Self::node_ns(Namespace::Html, tag, id, attributes, children)
+    }
+
+    /// Creates an element of an SVG or MathML namespace.
+    pub fn node_ns(
+        namespace: Namespace,
+        tag: &'static str,
+        id: Option<String>,
+        attributes: Vec<Attribute<Msg>>,
+        children: Vec<Html<Msg>>,
     ) -> Self {
         Self::Node(Node {
             tag,
+            namespace,
             id,
             attributes,
             children,
@@ -112,17 +562,72 @@ impl<Msg> Html<Msg> {
     }
 }
 
+impl<Msg: Clone + PartialEq + 'static> Html<Msg> {
+    /// Changes the message type of a view so it can be embedded in a parent view, e.g.
+    /// `counter::view(&model.counter).map(Message::Counter)`. The view is only wrapped, and it's
+    /// converted when the tree is resolved, with the functions of nested maps composed, so mapping
+    /// doesn't traverse it and `f` runs when an event fires.
+    ///
+    /// The handlers of the result are equal to the ones of the previous render if their messages
+    /// are and `f` is the same function that doesn't capture anything, like an enum variant. A
+    /// closure that captures something can't be compared, so its handlers are patched on every
+    /// render, see [Html::filter_map_with].
+    pub fn map<New: 'static>(self, f: impl Fn(Msg) -> New + 'static) -> Html<New> {
+        self.filter_map(move |msg| Some(f(msg)))
+    }
+
+    /// Like [Html::map] but returning `None` swallows the message.
+    pub fn filter_map<New: 'static, F>(self, f: F) -> Html<New>
+    where
+        F: Fn(Msg) -> Option<New> + 'static,
+    {
+        let id = (std::mem::size_of::<F>() == 0).then(|| map::id::<F>(()));
+        Html::Map(Map::new(self, Arc::new(f), id))
+    }
+
+    /// Like [Html::filter_map] but `f` takes what it needs from `state`, which is compared with
+    /// the one of the previous render, so the handlers stay equal while it doesn't change. `f`
+    /// itself is compared by its type, so it shouldn't capture anything.
+    pub fn filter_map_with<New: 'static, S, F>(self, state: S, f: F) -> Html<New>
+    where
+        S: PartialEq + 'static,
+        F: Fn(&S, Msg) -> Option<New> + 'static,
+    {
+        debug_assert_eq!(std::mem::size_of::<F>(), 0, "`f` captures something");
+
+        let state = Arc::new(state);
+        let id = map::id::<F>(state.clone());
+        Html::Map(Map::new(
+            self,
+            Arc::new(move |msg| f(&state, msg)),
+            Some(id),
+        ))
+    }
+}
+
 impl<Msg> Clone for Html<Msg> {
     fn clone(&self) -> Self {
         match self {
             Self::Node(arg0) => Self::Node(arg0.clone()),
             Self::Text(arg0) => Self::Text(arg0.clone()),
+            Self::UnsafeRawHtml(arg0) => Self::UnsafeRawHtml(arg0.clone()),
+            Self::Fragment(arg0) => Self::Fragment(arg0.clone()),
+            Self::Provider(arg0) => Self::Provider(arg0.clone()),
+            Self::Consumer(arg0) => Self::Consumer(arg0.clone()),
+            Self::Map(arg0) => Self::Map(arg0.clone()),
         }
     }
 }
 
-/// Creates a new node.
+/// Inserts markup without escaping it, e.g. rendered markdown or highlighted code. Nothing checks
+/// it, so the caller is responsible for sanitising anything that doesn't come from a trusted
+/// source. The DOM renderer places it inside of a `<roko-raw>` element with `display: contents`,
+/// and a string renderer has to emit it verbatim.
+pub fn unsafe_raw_html<Msg>(html: impl Into<String>) -> Html<Msg> {
+    Html::UnsafeRawHtml(html.into())
+}
 
+/// Creates a new text node.
 pub fn text<Msg, T: Into<Html<Msg>>>(text: T) -> Html<Msg> {
     text.into()
 }
@@ -144,3 +649,66 @@ impl<Msg> From<String> for Html<Msg> {
         Html::Text(val)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_every_attribute_with_a_value() {
+        let attrs: Attrs<()> = vec![
+            Attribute::Custom("title".to_string(), "Hi".to_string()),
+            Attribute::Custom("class".to_string(), "a".to_string()),
+            Attribute::Class(vec!["b".to_string()]),
+            Attribute::Style(vec![("color".to_string(), "red".to_string())]),
+            Attribute::Bool("disabled".to_string(), true),
+            Attribute::Bool("hidden".to_string(), false),
+            Attribute::Property("value".to_string(), PropertyValue::Number(3.0)),
+            Attribute::OnClick(Handler::message(())),
+        ];
+
+        let mut map = to_map(attrs).into_iter().collect::<Vec<_>>();
+        map.sort();
+
+        let expected = [
+            ("class", "a b"),
+            ("disabled", ""),
+            ("style", "color: red;"),
+            ("title", "Hi"),
+            ("value", "3"),
+        ];
+        assert_eq!(
+            map,
+            expected.map(|(key, value)| (key.to_string(), value.to_string()))
+        );
+    }
+
+    #[test]
+    fn converts_numbers_into_property_values() {
+        assert_eq!(PropertyValue::from(3u8), PropertyValue::Number(3.0));
+        assert_eq!(PropertyValue::from(-3i64), PropertyValue::Number(-3.0));
+        assert_eq!(PropertyValue::from(0.5f32), PropertyValue::Number(0.5));
+    }
+
+    #[test]
+    fn finds_namespaces_by_uri() {
+        for namespace in [Namespace::Html, Namespace::Svg, Namespace::MathMl] {
+            assert_eq!(Namespace::from_uri(namespace.uri()), Some(namespace));
+        }
+        assert_eq!(Namespace::from_uri("http://example.com"), None);
+    }
+
+    #[test]
+    fn finds_namespaces_of_prefixed_attributes() {
+        assert_eq!(
+            attribute_namespace("xlink:href"),
+            Some("http://www.w3.org/1999/xlink")
+        );
+        assert_eq!(
+            attribute_namespace("xml:lang"),
+            Some("http://www.w3.org/XML/1998/namespace")
+        );
+        assert_eq!(attribute_namespace("href"), None);
+        assert_eq!(attribute_namespace("data:x"), None);
+    }
+}
@@ -0,0 +1,349 @@
+//! Lazy [Html::map]. A mapped view keeps its own message type and the function that converts its
+//! messages, and it's only traversed when the tree is resolved. The functions of the maps around
+//! a view are composed there, so every node is visited once however deep the maps are nested, and
+//! they only run when an event fires.
+//!
+//! The handlers of a resolved view are new closures, so they carry an [Identity] to be compared
+//! with the ones of the previous render.
+
+use std::any::{Any, TypeId};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use crate::context::Scope;
+use crate::{Attribute, Handler, HandlerMap, Html};
+
+/// Message of a mapped view converted by the functions of its maps. Its type is the one of the
+/// root, which the view doesn't know.
+#[derive(Clone)]
+pub(crate) struct AnyMsg(Arc<dyn Any>);
+
+/// Value that can be compared with another one of any type.
+pub(crate) trait DynEq {
+    fn same(&self, other: &dyn DynEq) -> bool;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: PartialEq + 'static> DynEq for T {
+    fn same(&self, other: &dyn DynEq) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Callback compared by its pointer.
+struct SameCallback<Msg>(Arc<dyn Fn() -> Option<Msg>>);
+
+impl<Msg> PartialEq for SameCallback<Msg> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Id of a map: the type of its function and what it's compared with.
+pub(crate) fn id<F: 'static>(state: impl PartialEq + 'static) -> Arc<dyn DynEq> {
+    Arc::new((TypeId::of::<F>(), state))
+}
+
+/// What a handler of a mapped view is compared with: the handler it comes from and the ids of the
+/// maps around it, from the innermost one. It's `None` when one of the maps can't be compared, so
+/// it's never equal to another one.
+#[derive(Clone)]
+pub struct Identity(Option<Vec<Arc<dyn DynEq>>>);
+
+impl Identity {
+    fn of<Msg: PartialEq + 'static>(handler: &Handler<Msg>, maps: &Identity) -> Self {
+        let handler: Option<Vec<Arc<dyn DynEq>>> = match handler {
+            Handler::Message(msg) => Some(vec![Arc::new(msg.clone())]),
+            Handler::Callback(callback) => Some(vec![Arc::new(SameCallback(callback.clone()))]),
+            Handler::Mapped(_, Identity(ids)) => ids.clone(),
+        };
+
+        Self(handler.zip(maps.0.clone()).map(|(mut ids, maps)| {
+            ids.extend(maps);
+            ids
+        }))
+    }
+}
+
+impl PartialEq for Identity {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(l), Some(r)) => {
+                l.len() == r.len() && l.iter().zip(r).all(|(l, r)| l.same(r.as_ref()))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Debug for Identity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Identity").finish_non_exhaustive()
+    }
+}
+
+/// Converts a message of a mapped view into the one of the root, with the ids of the maps it
+/// goes through.
+#[derive(Clone)]
+struct Tagger {
+    convert: Arc<dyn Fn(Box<dyn Any>) -> Option<AnyMsg>>,
+    maps: Identity,
+}
+
+/// View of another message type and the function that converts its messages, see [Html::map].
+pub struct Map<Msg> {
+    view: Box<dyn MappedView>,
+    _msg: PhantomData<fn() -> Msg>,
+}
+
+impl<Msg: 'static> Map<Msg> {
+    /// Map of `html` with `f`, which is compared by `id`, or never equal without one.
+    pub(crate) fn new<Inner: Clone + PartialEq + 'static>(
+        html: Html<Inner>,
+        f: Arc<dyn Fn(Inner) -> Option<Msg>>,
+        id: Option<Arc<dyn DynEq>>,
+    ) -> Self {
+        Self {
+            view: Box::new(View { html, f, id }),
+            _msg: PhantomData,
+        }
+    }
+}
+
+impl<Msg> Debug for Map<Msg> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Map").finish_non_exhaustive()
+    }
+}
+
+impl<Msg> Clone for Map<Msg> {
+    fn clone(&self) -> Self {
+        Self {
+            view: self.view.clone_box(),
+            _msg: PhantomData,
+        }
+    }
+}
+
+/// A mapped view with its types erased, so the maps of any message type can be in the tree.
+trait MappedView {
+    /// Resolves the view into `out`, with `tagger` converting the messages of the map into the
+    /// ones of the root.
+    fn resolve(self: Box<Self>, scope: &mut Scope, tagger: &Tagger, out: &mut Vec<Html<AnyMsg>>);
+
+    fn clone_box(&self) -> Box<dyn MappedView>;
+}
+
+struct View<Inner, Msg> {
+    html: Html<Inner>,
+    f: Arc<dyn Fn(Inner) -> Option<Msg>>,
+    id: Option<Arc<dyn DynEq>>,
+}
+
+impl<Inner: Clone + PartialEq + 'static, Msg: 'static> MappedView for View<Inner, Msg> {
+    fn resolve(self: Box<Self>, scope: &mut Scope, tagger: &Tagger, out: &mut Vec<Html<AnyMsg>>) {
+        let f = self.f;
+        let convert = tagger.convert.clone();
+
+        let maps = self.id.zip(tagger.maps.0.clone()).map(|(id, outer)| {
+            let mut maps = vec![id];
+            maps.extend(outer);
+            maps
+        });
+
+        let composed = Tagger {
+            convert: Arc::new(move |msg| {
+                let msg = *msg.downcast::<Inner>().ok()?;
+                convert(Box::new(f(msg)?))
+            }),
+            maps: Identity(maps),
+        };
+
+        self.html.resolve_with(scope, &Erase(composed), out);
+    }
+
+    fn clone_box(&self) -> Box<dyn MappedView> {
+        Box::new(View {
+            html: self.html.clone(),
+            f: self.f.clone(),
+            id: self.id.clone(),
+        })
+    }
+}
+
+/// How [Html::resolve] turns the handlers and maps of a tree into the ones of its result.
+pub(crate) trait Convert<Msg, Out> {
+    fn attributes(&self, attributes: Vec<Attribute<Msg>>) -> Vec<Attribute<Out>>;
+
+    fn map(&self, map: Map<Msg>, scope: &mut Scope, out: &mut Vec<Html<Out>>);
+}
+
+/// Keeps the handlers of a tree with the message type of the root.
+pub(crate) struct Same;
+
+impl<Msg: Clone + 'static> Convert<Msg, Msg> for Same {
+    fn attributes(&self, attributes: Vec<Attribute<Msg>>) -> Vec<Attribute<Msg>> {
+        attributes
+    }
+
+    fn map(&self, map: Map<Msg>, scope: &mut Scope, out: &mut Vec<Html<Msg>>) {
+        force(map, scope, out)
+    }
+}
+
+/// Makes the handlers of a mapped view send messages of the root.
+struct Erase(Tagger);
+
+impl<Msg: Clone + PartialEq + 'static> Convert<Msg, AnyMsg> for Erase {
+    fn attributes(&self, attributes: Vec<Attribute<Msg>>) -> Vec<Attribute<AnyMsg>> {
+        let tagger = self.0.clone();
+
+        let f: HandlerMap<Msg, AnyMsg> = Arc::new(move |handler: Handler<Msg>| {
+            let identity = Identity::of(&handler, &tagger.maps);
+            let convert = tagger.convert.clone();
+            let callback = move || {
+                handler
+                    .fire()
+                    .and_then(|msg| convert(Box::new((*msg).clone())))
+            };
+
+            Handler::Mapped(Arc::new(callback), identity)
+        });
+
+        attributes
+            .into_iter()
+            .map(|attribute| attribute.map_handlers(&f))
+            .collect()
+    }
+
+    fn map(&self, map: Map<Msg>, scope: &mut Scope, out: &mut Vec<Html<AnyMsg>>) {
+        map.view.resolve(scope, &self.0, out)
+    }
+}
+
+/// Gives the handlers of a resolved map the message type of the root back.
+struct Downcast;
+
+impl<Msg: Clone + 'static> Convert<AnyMsg, Msg> for Downcast {
+    fn attributes(&self, attributes: Vec<Attribute<AnyMsg>>) -> Vec<Attribute<Msg>> {
+        let f: HandlerMap<AnyMsg, Msg> = Arc::new(|handler: Handler<AnyMsg>| {
+            let identity = match &handler {
+                Handler::Mapped(_, identity) => identity.clone(),
+                _ => Identity(None),
+            };
+            let callback = move || {
+                handler
+                    .fire()
+                    .and_then(|msg| msg.0.downcast_ref::<Msg>().cloned())
+            };
+
+            Handler::Mapped(Arc::new(callback), identity)
+        });
+
+        attributes
+            .into_iter()
+            .map(|attribute| attribute.map_handlers(&f))
+            .collect()
+    }
+
+    fn map(&self, _: Map<AnyMsg>, _: &mut Scope, _: &mut Vec<Html<Msg>>) {
+        debug_assert!(false, "resolved maps don't have maps left");
+    }
+}
+
+/// Resolves a map in a tree of the root. Its view is resolved with the functions of the maps
+/// inside of it composed, and then given the message type of the root.
+fn force<Msg: Clone + 'static>(map: Map<Msg>, scope: &mut Scope, out: &mut Vec<Html<Msg>>) {
+    let root = Tagger {
+        convert: Arc::new(|msg| Some(AnyMsg(Arc::from(msg)))),
+        maps: Identity(Some(Vec::new())),
+    };
+
+    let mut erased = Vec::new();
+    map.view.resolve(scope, &root, &mut erased);
+
+    for html in erased {
+        html.resolve_with(scope, &Downcast, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{consume, provider};
+    use crate::{text, InputEvent, Listener};
+
+    fn button<Msg>(attributes: Vec<Attribute<Msg>>) -> Html<Msg> {
+        Html::node("button", None, attributes, vec![text("go")])
+    }
+
+    /// Only attribute of a resolved element.
+    fn attribute<Msg: Clone + 'static>(html: Html<Msg>) -> Attribute<Msg> {
+        match html.resolve() {
+            Html::Node(mut node) if node.attributes.len() == 1 => node.attributes.remove(0),
+            other => panic!("expected an element with an attribute, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn composes_nested_maps() {
+        let view = button(vec![Attribute::OnClick(Handler::Message(Arc::new(1)))])
+            .map(|n: i32| n + 1)
+            .map(|n| n * 10)
+            .map(|n| format!("{n}"));
+
+        let Attribute::OnClick(handler) = attribute(view) else {
+            panic!("expected a click handler");
+        };
+
+        assert_eq!(handler.fire().as_deref(), Some(&"20".to_string()));
+    }
+
+    #[test]
+    fn swallows_filtered_messages() {
+        let view = button(vec![Attribute::OnClick(Handler::Message(Arc::new(1)))])
+            .filter_map(|_: i32| None::<i32>)
+            .map(|n| n + 1);
+
+        let Attribute::OnClick(handler) = attribute(view) else {
+            panic!("expected a click handler");
+        };
+
+        assert_eq!(handler.fire(), None);
+    }
+
+    #[test]
+    fn maps_listeners() {
+        let view = button(vec![Attribute::OnInput(Listener::new(
+            |event: InputEvent| Some(event.value.len()),
+        ))])
+        .map(|len| len * 2);
+
+        let Attribute::OnInput(listener) = attribute(view) else {
+            panic!("expected an input listener");
+        };
+
+        let event = InputEvent {
+            value: "abc".to_string(),
+            checked: false,
+        };
+        assert_eq!(listener.fire(event).as_deref(), Some(&6));
+    }
+
+    #[test]
+    fn resolves_contexts_inside_of_maps() {
+        let inner = consume(|label: &&'static str| text::<i32, _>(*label)).map(|n| n + 1);
+        let view = provider("hi", None, Vec::new(), vec![inner]);
+
+        match view.resolve() {
+            Html::Text(text) => assert_eq!(text, "hi"),
+            other => panic!("expected the text of the context, got {other:?}"),
+        }
+    }
+}
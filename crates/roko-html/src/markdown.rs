@@ -0,0 +1,619 @@
+//! Markdown rendered into [Html] nodes, so it's diffed like any other view instead of being set
+//! with `innerHTML`, e.g. for a README fetched with `roko_http::get`. It's CommonMark with tables
+//! and footnotes. The `markdown!` and `include_markdown!` macros render it when compiling and turn
+//! the nodes into the code that builds them.
+//!
+//! Code blocks of the languages of [highlight] are highlighted. HTML blocks go through the
+//! default [Sanitizer], and so does inline HTML like `<kbd>`, a tag at a time: an allowed tag
+//! opens an element that is closed by its closing tag or the end of its parent, and the others
+//! are dropped, keeping the text after them.
+//!
+//! ```ignore
+//! let readme = Renderer::new()
+//!     .link(|link| html! { <a href={link.url} target="_blank">{link.children}</a> })
+//!     .render(&body);
+//! ```
+
+use std::collections::HashMap;
+
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+
+use crate::parser::VOID_ELEMENTS;
+use crate::{highlight, Attribute, Attrs, Children, Html, Sanitizer};
+
+/// Link given to [Renderer::link], with its rendered text.
+pub struct Link<Msg> {
+    pub url: String,
+    /// Empty if the link has no title.
+    pub title: String,
+    pub children: Children<Msg>,
+}
+
+/// Image given to [Renderer::image].
+pub struct Image {
+    pub url: String,
+    /// Empty if the image has no title.
+    pub title: String,
+    /// Text of the description of the image, without its formatting.
+    pub alt: String,
+}
+
+/// Renders markdown, with hooks that change how links and images are rendered. By default they
+/// are rendered as `<a>` and `<img>`.
+pub struct Renderer<Msg> {
+    link: Box<dyn Fn(Link<Msg>) -> Html<Msg>>,
+    image: Box<dyn Fn(Image) -> Html<Msg>>,
+}
+
+impl<Msg> Default for Renderer<Msg> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Msg> Renderer<Msg> {
+    pub fn new() -> Self {
+        Self {
+            link: Box::new(|link| {
+                Element::new("a")
+                    .attribute("href", &link.url)
+                    .optional_attribute("title", &link.title)
+                    .children(link.children)
+                    .build()
+            }),
+            image: Box::new(|image| {
+                Element::new("img")
+                    .attribute("src", &image.url)
+                    .attribute("alt", &image.alt)
+                    .optional_attribute("title", &image.title)
+                    .build()
+            }),
+        }
+    }
+
+    /// Renders links with `f`, e.g. to open external ones in a new tab or to send a message for
+    /// the ones of the application.
+    pub fn link(mut self, f: impl Fn(Link<Msg>) -> Html<Msg> + 'static) -> Self {
+        self.link = Box::new(f);
+        self
+    }
+
+    /// Renders images with `f`, e.g. to load them lazily or from a CDN.
+    pub fn image(mut self, f: impl Fn(Image) -> Html<Msg> + 'static) -> Self {
+        self.image = Box::new(f);
+        self
+    }
+
+    /// Renders `markdown`. The YAML front-matter of a post, between `---` lines at its start, is
+    /// skipped.
+    pub fn render(&self, markdown: &str) -> Html<Msg> {
+        let options = Options::ENABLE_TABLES
+            | Options::ENABLE_FOOTNOTES
+            | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS;
+
+        let mut state = State {
+            renderer: self,
+            stack: vec![Element::new("")],
+            alignments: Vec::new(),
+            column: 0,
+            language: None,
+            footnotes: Vec::new(),
+            definitions: HashMap::new(),
+        };
+
+        for event in Parser::new_ext(markdown, options) {
+            state.event(event);
+        }
+
+        state.finish()
+    }
+}
+
+/// Renders markdown with the default [Renderer].
+pub fn render<Msg>(markdown: &str) -> Html<Msg> {
+    Renderer::new().render(markdown)
+}
+
+/// Element that is being built, with the children that were rendered so far.
+struct Element<Msg> {
+    tag: &'static str,
+    attributes: Attrs<Msg>,
+    children: Children<Msg>,
+    /// URL and title of a link or an image, which are rendered by the hooks when they end, or the
+    /// label of a footnote.
+    target: Option<(String, String)>,
+    /// Opened by an inline HTML tag.
+    inline_html: bool,
+}
+
+impl<Msg> Element<Msg> {
+    fn new(tag: &'static str) -> Self {
+        Self {
+            tag,
+            attributes: Vec::new(),
+            children: Vec::new(),
+            target: None,
+            inline_html: false,
+        }
+    }
+
+    fn attribute(mut self, name: &str, value: &str) -> Self {
+        self.attributes
+            .push(Attribute::Custom(name.to_string(), value.to_string()));
+        self
+    }
+
+    fn optional_attribute(self, name: &str, value: &str) -> Self {
+        match value {
+            "" => self,
+            value => self.attribute(name, value),
+        }
+    }
+
+    fn class(mut self, class: &str) -> Self {
+        self.attributes
+            .push(Attribute::Class(vec![class.to_string()]));
+        self
+    }
+
+    fn children(mut self, children: Children<Msg>) -> Self {
+        self.children = children;
+        self
+    }
+
+    fn push(&mut self, node: Html<Msg>) {
+        // Text is split around escapes and references, e.g. `a &amp; b` gives three events.
+        match (self.children.last_mut(), node) {
+            (Some(Html::Text(text)), Html::Text(more)) => text.push_str(&more),
+            (_, node) => self.children.push(node),
+        }
+    }
+
+    fn build(self) -> Html<Msg> {
+        Html::node(self.tag, None, self.attributes, self.children)
+    }
+}
+
+/// Text of nodes without their tags, e.g. for the alt text of an image.
+fn text<Msg>(nodes: &[Html<Msg>]) -> String {
+    nodes
+        .iter()
+        .map(|node| match node {
+            Html::Text(text) => text.clone(),
+            Html::Node(node) => text(&node.children),
+            _ => String::new(),
+        })
+        .collect()
+}
+
+fn heading(level: usize) -> &'static str {
+    match level {
+        1 => "h1",
+        2 => "h2",
+        3 => "h3",
+        4 => "h4",
+        5 => "h5",
+        _ => "h6",
+    }
+}
+
+struct State<'a, Msg> {
+    renderer: &'a Renderer<Msg>,
+    /// Open elements, the first one is the root.
+    stack: Vec<Element<Msg>>,
+    /// Alignment of the columns of the current table.
+    alignments: Vec<Alignment>,
+    column: usize,
+    /// Language of the current code block, which is highlighted when it ends.
+    language: Option<String>,
+    /// Labels of the footnotes, numbered in the order they are first referenced.
+    footnotes: Vec<String>,
+    /// Content of each footnote by its label.
+    definitions: HashMap<String, Children<Msg>>,
+}
+
+impl<Msg> State<'_, Msg> {
+    fn parent(&mut self) -> &mut Element<Msg> {
+        self.stack.last_mut().expect("the root is never closed")
+    }
+
+    /// Number of a referenced footnote and whether it was referenced before.
+    fn footnote(&mut self, label: &str) -> (usize, bool) {
+        match self.footnotes.iter().position(|other| other == label) {
+            Some(at) => (at + 1, true),
+            None => {
+                self.footnotes.push(label.to_string());
+                (self.footnotes.len(), false)
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag) -> Element<Msg> {
+        match tag {
+            Tag::Paragraph => Element::new("p"),
+            Tag::Heading { level, .. } => Element::new(heading(level as usize)),
+            Tag::BlockQuote(_) => Element::new("blockquote"),
+            // The `<pre>` around it is added when it ends.
+            Tag::CodeBlock(kind) => {
+                let language = match &kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next(),
+                    CodeBlockKind::Indented => None,
+                };
+
+                self.language = language.map(ToString::to_string);
+
+                match language {
+                    Some(language) => Element::new("code").class(&format!("language-{language}")),
+                    None => Element::new("code"),
+                }
+            }
+            Tag::List(Some(1)) => Element::new("ol"),
+            Tag::List(Some(start)) => Element::new("ol").attribute("start", &start.to_string()),
+            Tag::List(None) => Element::new("ul"),
+            Tag::Item => Element::new("li"),
+            Tag::Emphasis => Element::new("em"),
+            Tag::Strong => Element::new("strong"),
+            Tag::Strikethrough => Element::new("del"),
+            Tag::Superscript => Element::new("sup"),
+            Tag::Subscript => Element::new("sub"),
+            Tag::Link {
+                dest_url, title, ..
+            } => Element {
+                target: Some((dest_url.into_string(), title.into_string())),
+                ..Element::new("a")
+            },
+            Tag::Image {
+                dest_url, title, ..
+            } => Element {
+                target: Some((dest_url.into_string(), title.into_string())),
+                ..Element::new("img")
+            },
+            // The rows after the head are placed in a `<tbody>` when it ends.
+            Tag::Table(alignments) => {
+                self.alignments = alignments;
+                Element::new("table")
+            }
+            // The cells of the head aren't in a row, it's added when it ends.
+            Tag::TableHead => {
+                self.column = 0;
+                Element::new("thead")
+            }
+            Tag::TableRow => {
+                self.column = 0;
+                Element::new("tr")
+            }
+            Tag::TableCell => {
+                let cell = match self.parent().tag {
+                    "thead" => Element::new("th"),
+                    _ => Element::new("td"),
+                };
+
+                let alignment = self.alignments.get(self.column).copied();
+                self.column += 1;
+
+                let alignment = match alignment {
+                    Some(Alignment::Left) => "left",
+                    Some(Alignment::Center) => "center",
+                    Some(Alignment::Right) => "right",
+                    Some(Alignment::None) | None => return cell,
+                };
+
+                let style = vec![("text-align".to_string(), alignment.to_string())];
+                Element {
+                    attributes: vec![Attribute::Style(style)],
+                    ..cell
+                }
+            }
+            Tag::FootnoteDefinition(label) => Element {
+                target: Some((label.into_string(), String::new())),
+                ..Element::new("li")
+            },
+            // Their content is handled when they end.
+            Tag::HtmlBlock | Tag::MetadataBlock(_) => Element::new(""),
+            Tag::DefinitionList | Tag::DefinitionListTitle | Tag::DefinitionListDefinition => {
+                unreachable!("the extension isn't enabled")
+            }
+        }
+    }
+
+    /// Closes the innermost open element, placing it in its parent.
+    fn close(&mut self) {
+        let element = self.stack.pop().expect("the root is never closed");
+        self.parent().push(element.build())
+    }
+
+    /// Adds a tag of inline HTML, sanitised on its own.
+    fn inline_html(&mut self, html: &str) {
+        if let Some(name) = html.strip_prefix("</") {
+            let name = name.trim_end_matches('>').trim();
+
+            if self
+                .stack
+                .last()
+                .is_some_and(|open| open.inline_html && open.tag.eq_ignore_ascii_case(name))
+            {
+                self.close();
+            }
+            return;
+        }
+
+        // Without its closing tag the element is sanitised with no children.
+        let Some(Html::Node(node)) = Sanitizer::default().sanitize(html).pop() else {
+            return;
+        };
+
+        if VOID_ELEMENTS.contains(&node.tag) {
+            self.parent().push(Html::Node(node));
+        } else {
+            self.stack.push(Element {
+                attributes: node.attributes,
+                inline_html: true,
+                ..Element::new(node.tag)
+            });
+        }
+    }
+
+    fn end(&mut self, end: TagEnd) {
+        // Inline HTML that isn't closed ends with its parent.
+        while self.stack.last().is_some_and(|open| open.inline_html) {
+            self.close();
+        }
+
+        let mut element = self.stack.pop().expect("every end has a start");
+
+        let node = match end {
+            TagEnd::CodeBlock => {
+                let language = self.language.take().unwrap_or_default();
+                if let Some(code) = highlight::highlight(&language, &text(&element.children)) {
+                    element.children = code;
+                }
+                Element::new("pre").children(vec![element.build()]).build()
+            }
+            TagEnd::Link => {
+                let (url, title) = element.target.expect("links have a target");
+                (self.renderer.link)(Link {
+                    url,
+                    title,
+                    children: element.children,
+                })
+            }
+            TagEnd::Image => {
+                let (url, title) = element.target.expect("images have a target");
+                (self.renderer.image)(Image {
+                    url,
+                    title,
+                    alt: text(&element.children),
+                })
+            }
+            TagEnd::TableHead => {
+                let row = Element::new("tr").children(element.children).build();
+                Element::new("thead").children(vec![row]).build()
+            }
+            TagEnd::Table => {
+                let rows = element.children.split_off(element.children.len().min(1));
+                if !rows.is_empty() {
+                    element.push(Element::new("tbody").children(rows).build());
+                }
+                element.build()
+            }
+            // Definitions are placed at the end, with a link back to the first reference.
+            TagEnd::FootnoteDefinition => {
+                let (label, _) = element.target.take().expect("footnotes have a label");
+                let back = Element::new("a")
+                    .attribute("href", &format!("#fnref-{label}"))
+                    .class("footnote-back")
+                    .children(vec![Html::Text("↩".to_string())]);
+                element.push(back.build());
+
+                self.definitions.insert(label, element.children);
+                return;
+            }
+            TagEnd::HtmlBlock => {
+                let html = text(&element.children);
+                let nodes = Sanitizer::default().sanitize(html.trim_end());
+                self.parent().children.extend(nodes);
+                return;
+            }
+            TagEnd::MetadataBlock(_) => return,
+            _ => element.build(),
+        };
+
+        self.parent().push(node)
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => {
+                let element = self.start(tag);
+                self.stack.push(element)
+            }
+            Event::End(end) => self.end(end),
+            Event::Text(text) | Event::Html(text) => {
+                self.parent().push(Html::Text(text.into_string()))
+            }
+            Event::InlineHtml(html) => self.inline_html(&html),
+            Event::Code(code) => {
+                let code = Element::new("code").children(vec![Html::Text(code.into_string())]);
+                self.parent().push(code.build())
+            }
+            Event::SoftBreak => self.parent().push(Html::Text("\n".to_string())),
+            Event::HardBreak => self.parent().push(Element::new("br").build()),
+            Event::Rule => self.parent().push(Element::new("hr").build()),
+            Event::FootnoteReference(label) => {
+                let (number, seen) = self.footnote(&label);
+                let mut link = Element::new("a").attribute("href", &format!("#fn-{label}"));
+
+                // Every reference links to the footnote, which links back to the first one.
+                if !seen {
+                    link = link.attribute("id", &format!("fnref-{label}"));
+                }
+
+                let link = link.children(vec![Html::Text(number.to_string())]);
+                let reference = Element::new("sup")
+                    .class("footnote-reference")
+                    .children(vec![link.build()]);
+
+                self.parent().push(reference.build())
+            }
+            Event::TaskListMarker(_) | Event::InlineMath(_) | Event::DisplayMath(_) => {
+                unreachable!("the extension isn't enabled")
+            }
+        }
+    }
+
+    fn finish(mut self) -> Html<Msg> {
+        let mut root = self.stack.pop().expect("the root is never closed");
+
+        // Footnotes that are never defined are left out, so the items are numbered with `value`.
+        // The ones that are never referenced aren't in the list.
+        let footnotes: Vec<_> = self
+            .footnotes
+            .into_iter()
+            .enumerate()
+            .filter_map(|(at, label)| {
+                let item = Element::new("li")
+                    .attribute("id", &format!("fn-{label}"))
+                    .attribute("value", &(at + 1).to_string())
+                    .children(self.definitions.remove(&label)?);
+                Some(item.build())
+            })
+            .collect();
+
+        if !footnotes.is_empty() {
+            let list = Element::new("ol").children(footnotes).build();
+            let section = Element::new("section")
+                .class("footnotes")
+                .children(vec![Element::new("hr").build(), list]);
+            root.push(section.build());
+        }
+
+        match root.children.len() {
+            1 => root.children.remove(0),
+            _ => Html::Fragment(root.children),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Markup of rendered nodes, with the attributes as they are debugged.
+    fn markup(html: &Html<()>) -> String {
+        match html {
+            Html::Node(node) => {
+                let attributes = node
+                    .attributes
+                    .iter()
+                    .map(|attribute| format!(" {attribute:?}"))
+                    .collect::<String>();
+                let children = node.children.iter().map(markup).collect::<String>();
+                format!("<{0}{attributes}>{children}</{0}>", node.tag)
+            }
+            Html::Text(text) => text.clone(),
+            Html::Fragment(children) => children.iter().map(markup).collect(),
+            other => panic!("unexpected node {other:?}"),
+        }
+    }
+
+    fn render(markdown: &str) -> String {
+        markup(&super::render(markdown))
+    }
+
+    #[test]
+    fn aligns_table_columns() {
+        let table = render("| a | b | c |\n|:--|:-:|---|\n| 1 | 2 | 3 |");
+
+        assert_eq!(
+            table,
+            "<table><thead><tr>\
+             <th Style([(\"text-align\", \"left\")])>a</th>\
+             <th Style([(\"text-align\", \"center\")])>b</th>\
+             <th>c</th></tr></thead>\
+             <tbody><tr>\
+             <td Style([(\"text-align\", \"left\")])>1</td>\
+             <td Style([(\"text-align\", \"center\")])>2</td>\
+             <td>3</td></tr></tbody></table>"
+        );
+    }
+
+    #[test]
+    fn numbers_footnotes_in_order_of_reference() {
+        let html = render("[^a]: A\n\nb[^b] a[^a] b[^b]\n\n[^b]: B\n[^c]: Unused");
+
+        let reference = |label: &str, number: usize, id: bool| {
+            let id = if id {
+                format!(" Custom(\"id\", \"fnref-{label}\")")
+            } else {
+                String::new()
+            };
+            format!(
+                "<sup Class([\"footnote-reference\"])>\
+                 <a Custom(\"href\", \"#fn-{label}\"){id}>{number}</a></sup>"
+            )
+        };
+        let footnote = |label: &str, number: usize, text: &str| {
+            format!(
+                "<li Custom(\"id\", \"fn-{label}\") Custom(\"value\", \"{number}\")>\
+                 <p>{text}</p><a Custom(\"href\", \"#fnref-{label}\") \
+                 Class([\"footnote-back\"])>↩</a></li>"
+            )
+        };
+
+        assert_eq!(
+            html,
+            format!(
+                "<p>b{} a{} b{}</p><section Class([\"footnotes\"])><hr></hr><ol>{}{}</ol></section>",
+                reference("b", 1, true),
+                reference("a", 2, true),
+                reference("b", 1, false),
+                footnote("b", 1, "B"),
+                footnote("a", 2, "A"),
+            )
+        );
+    }
+
+    #[test]
+    fn sanitizes_html_blocks() {
+        assert_eq!(
+            render("<div onclick=\"alert(1)\">\n<script>alert(1)</script><b>hi</b>\n</div>"),
+            "<div>\n<b>hi</b>\n</div>"
+        );
+    }
+
+    #[test]
+    fn sanitizes_inline_html() {
+        assert_eq!(
+            render("Press <kbd>Ctrl</kbd>,<br>then <b onclick=\"x()\">go</b>"),
+            "<p>Press <kbd>Ctrl</kbd>,<br></br>then <b>go</b></p>"
+        );
+        assert_eq!(
+            render("a <marquee>b</marquee> <em>c"),
+            "<p>a b <em>c</em></p>"
+        );
+    }
+
+    #[test]
+    fn skips_front_matter() {
+        assert_eq!(render("---\ntitle: Hi\n---\n# Post"), "<h1>Post</h1>");
+    }
+
+    #[test]
+    fn renders_links_and_images_with_hooks() {
+        let renderer = Renderer::<()>::new()
+            .link(|link| {
+                Element::new("a")
+                    .attribute("href", &link.url)
+                    .attribute("target", "_blank")
+                    .children(link.children)
+                    .build()
+            })
+            .image(|image| Html::Text(format!("[{}: {}]", image.alt, image.url)));
+
+        assert_eq!(
+            markup(&renderer.render("[a *b*](/x) ![the *cat*](c.png \"Cat\")")),
+            "<p><a Custom(\"href\", \"/x\") Custom(\"target\", \"_blank\")>a <em>b</em></a> \
+             [the cat: c.png]</p>"
+        );
+    }
+}
@@ -0,0 +1,576 @@
+//! HTML parser that builds [Html] nodes at runtime, e.g. for templates loaded from a CMS, so they
+//! are rendered and diffed like any other view. The tree is built like browsers do: void elements
+//! like `<br>` don't need a closing tag, optional closing tags like the one of `<li>` are implied,
+//! and character references in text and attribute values are decoded. Problems are recorded
+//! with their position, [parse] fails on the first one and the sanitiser ignores them.
+
+use std::fmt::{self, Display};
+
+use crate::spec::{self, ElementSpec};
+use crate::{class_names, entities, style_declarations, Attribute, Html, PropertyValue};
+
+/// Elements that can't have children, so they never need a closing tag.
+pub(crate) const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements whose content is text until their closing tag, e.g. the code of a `<script>`.
+const RAW_TEXT_ELEMENTS: &[&str] = &[
+    "iframe", "noembed", "noframes", "noscript", "script", "style", "textarea", "title", "xmp",
+];
+
+/// Elements that close an open `<p>` when they start.
+const CLOSES_PARAGRAPH: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+/// Whether starting `opening` closes the open element `open`, e.g. a `<li>` closes the previous
+/// one, so `<li>one<li>two` are siblings.
+fn implies_end(opening: &str, open: &str) -> bool {
+    match open {
+        "p" => CLOSES_PARAGRAPH.contains(&opening),
+        "li" => opening == "li",
+        "dt" | "dd" => matches!(opening, "dt" | "dd"),
+        "option" => matches!(opening, "option" | "optgroup"),
+        "optgroup" => opening == "optgroup",
+        "td" | "th" => matches!(opening, "td" | "th" | "tr"),
+        "tr" => opening == "tr",
+        _ => false,
+    }
+}
+
+/// Elements whose closing tag can be left out, so they don't need one before the end of their
+/// parent.
+const OPTIONAL_END: &[&str] = &[
+    "caption", "colgroup", "dd", "dt", "li", "optgroup", "option", "p", "rp", "rt", "tbody", "td",
+    "tfoot", "th", "thead", "tr",
+];
+
+/// Problem in the markup, with the position where it's found. Lines and columns start at 1 and
+/// columns count characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    /// Offset in bytes.
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl ParseError {
+    fn new(input: &str, offset: usize, message: String) -> Self {
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |at| at + 1);
+
+        Self {
+            message,
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+    /// Offset of the `<` of its start tag.
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Node {
+    Element(Element),
+    Text(String),
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    errors: Vec<ParseError>,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn error(&mut self, offset: usize, message: impl Into<String>) {
+        let error = ParseError::new(self.input, offset, message.into());
+        self.errors.push(error);
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        if self.rest().starts_with(prefix) {
+            self.pos += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Moves past `end`. When it's missing the input ends without it, which is reported as
+    /// `message` at `start`.
+    fn skip_past(&mut self, end: &str, start: usize, message: &str) {
+        match self.rest().find(end) {
+            Some(at) => self.pos += at + end.len(),
+            None => {
+                self.pos = self.input.len();
+                self.error(start, message);
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.input.len() - trimmed.len();
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let len = rest.find(|ch| !f(ch)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn name(&mut self) -> String {
+        self.take_while(|ch| !ch.is_whitespace() && !matches!(ch, '/' | '>' | '='))
+            .to_ascii_lowercase()
+    }
+
+    fn attribute_value(&mut self) -> String {
+        self.skip_whitespace();
+
+        if !self.eat("=") {
+            return String::new();
+        }
+
+        self.skip_whitespace();
+        let start = self.pos;
+
+        let value = match self.rest().chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                self.pos += 1;
+                let value = self.take_while(|ch| ch != quote);
+
+                if !self.eat(&quote.to_string()) {
+                    self.error(
+                        start,
+                        format!("attribute value is never closed, add a `{quote}`"),
+                    );
+                }
+
+                value
+            }
+            _ => {
+                // An unquoted value ends before the `/>` of a self-closing tag, e.g. `<path d=x/>`.
+                let rest = self.rest();
+                let len = rest
+                    .char_indices()
+                    .find(|&(at, ch)| {
+                        ch.is_whitespace() || ch == '>' || rest[at..].starts_with("/>")
+                    })
+                    .map_or(rest.len(), |(at, _)| at);
+                self.pos += len;
+                &rest[..len]
+            }
+        };
+
+        entities::decode(value)
+    }
+
+    /// Parses a start tag after its `<`, returning the element and whether it closes itself.
+    fn start_tag(&mut self, offset: usize) -> (Element, bool) {
+        let mut element = Element {
+            name: self.name(),
+            attributes: Vec::new(),
+            children: Vec::new(),
+            offset,
+        };
+
+        loop {
+            self.skip_whitespace();
+
+            if self.rest().is_empty() {
+                self.error(
+                    offset,
+                    format!("`<{}` is never closed, add a `>`", element.name),
+                );
+                return (element, true);
+            }
+
+            if self.eat(">") {
+                return (element, false);
+            }
+
+            if self.eat("/>") {
+                return (element, true);
+            }
+
+            if self.eat("/") {
+                continue;
+            }
+
+            let start = self.pos;
+            let name = self.name();
+            let value = self.attribute_value();
+
+            if element.attributes.iter().any(|(other, _)| *other == name) {
+                self.error(
+                    start,
+                    format!("`{name}` is repeated, the first one is used"),
+                );
+            } else {
+                element.attributes.push((name, value));
+            }
+        }
+    }
+
+    /// Text of a raw text element, up to its closing tag.
+    fn raw_text(&mut self, element: &Element) -> String {
+        let closing = format!("</{}", element.name);
+        let lower = self.rest().to_ascii_lowercase();
+
+        let len = lower.find(&closing).unwrap_or_else(|| {
+            self.error(
+                element.offset,
+                format!("`<{0}>` is never closed, add a `</{0}>`", element.name),
+            );
+            lower.len()
+        });
+
+        let text = &self.rest()[..len];
+        self.pos += len;
+
+        match element.name.as_str() {
+            "textarea" | "title" => entities::decode(text),
+            _ => text.to_string(),
+        }
+    }
+}
+
+/// Appends a node to the innermost open element, joining text that was split by a stray `<` or a
+/// comment.
+fn append(stack: &mut [Element], roots: &mut Vec<Node>, node: Node) {
+    let siblings = match stack.last_mut() {
+        Some(parent) => &mut parent.children,
+        None => roots,
+    };
+
+    match (siblings.last_mut(), node) {
+        (Some(Node::Text(text)), Node::Text(more)) => text.push_str(&more),
+        (_, node) => siblings.push(node),
+    }
+}
+
+/// Closes the innermost open element.
+fn close(stack: &mut Vec<Element>, roots: &mut Vec<Node>) {
+    if let Some(element) = stack.pop() {
+        append(stack, roots, Node::Element(element));
+    }
+}
+
+/// Builds the tree of `input`. It never fails: stray closing tags are ignored and open elements
+/// are closed at the end, the problems are returned next to it.
+pub(crate) fn tree(input: &str) -> (Vec<Node>, Vec<ParseError>) {
+    let mut parser = Parser {
+        input,
+        pos: 0,
+        errors: Vec::new(),
+    };
+    let mut roots = Vec::new();
+    let mut stack: Vec<Element> = Vec::new();
+
+    while !parser.rest().is_empty() {
+        let rest = parser.rest();
+        let start = parser.pos;
+
+        if parser.eat("<!--") {
+            parser.skip_past("-->", start, "comment is never closed, add a `-->`");
+        } else if parser.eat("<!") || parser.eat("<?") {
+            parser.skip_past(">", start, "declaration is never closed, add a `>`");
+        } else if rest.starts_with("</")
+            && rest[2..].starts_with(|ch: char| ch.is_ascii_alphabetic())
+        {
+            parser.pos += 2;
+            let name = parser.name();
+            parser.skip_past(
+                ">",
+                start,
+                &format!("`</{name}` is never closed, add a `>`"),
+            );
+
+            let Some(at) = stack.iter().rposition(|element| element.name == name) else {
+                parser.error(start, format!("`</{name}>` doesn't close any tag"));
+                continue;
+            };
+
+            // The elements in between are closed too, which is only fine for optional tags.
+            if let Some(open) = stack[at + 1..]
+                .iter()
+                .rev()
+                .find(|open| !OPTIONAL_END.contains(&open.name.as_str()))
+            {
+                let message = format!(
+                    "expected `</{0}>` to close `<{0}>`, found `</{name}>`",
+                    open.name
+                );
+                parser.error(start, message);
+            }
+
+            while stack.len() > at {
+                close(&mut stack, &mut roots);
+            }
+        } else if rest.starts_with('<')
+            && rest[1..].starts_with(|ch: char| ch.is_ascii_alphabetic())
+        {
+            parser.pos += 1;
+            let (mut element, closed) = parser.start_tag(start);
+
+            while stack
+                .last()
+                .is_some_and(|open| implies_end(&element.name, &open.name))
+            {
+                close(&mut stack, &mut roots);
+            }
+
+            if closed || VOID_ELEMENTS.contains(&element.name.as_str()) {
+                append(&mut stack, &mut roots, Node::Element(element));
+            } else if RAW_TEXT_ELEMENTS.contains(&element.name.as_str()) {
+                let text = parser.raw_text(&element);
+                if !text.is_empty() {
+                    element.children.push(Node::Text(text));
+                }
+                // The closing tag, which is missing if `raw_text` reached the end.
+                if !parser.rest().is_empty() {
+                    let message = format!("`</{}` is never closed, add a `>`", element.name);
+                    parser.skip_past(">", parser.pos, &message);
+                }
+                append(&mut stack, &mut roots, Node::Element(element));
+            } else {
+                stack.push(element);
+            }
+        } else {
+            // A `<` that doesn't start a tag is text.
+            let first = rest.chars().next().map_or(1, char::len_utf8);
+            let len = rest[first..].find('<').map_or(rest.len(), |at| at + first);
+            parser.pos += len;
+            let text = entities::decode(&rest[..len]);
+            append(&mut stack, &mut roots, Node::Text(text));
+        }
+    }
+
+    for open in stack.iter().rev() {
+        if !OPTIONAL_END.contains(&open.name.as_str()) {
+            let message = format!("`<{0}>` is never closed, add a `</{0}>`", open.name);
+            parser.error(open.offset, message);
+        }
+    }
+
+    while !stack.is_empty() {
+        close(&mut stack, &mut roots);
+    }
+
+    (roots, parser.errors)
+}
+
+/// Builds an attribute like `html!` does, so parsed nodes diff in the same way. Names are
+/// lowercased by the parser, their case is taken from the spec.
+pub(crate) fn attribute<Msg>(element: &ElementSpec, name: String, value: String) -> Attribute<Msg> {
+    let kind = spec::spec().attribute_ignore_case(element, &name);
+    let name = kind.map_or(name, |kind| kind.name.to_string());
+
+    match kind {
+        _ if name == "class" => Attribute::Class(class_names(&value).collect()),
+        _ if name == "style" => Attribute::Style(style_declarations(&value)),
+        Some(kind) if kind.property && kind.boolean => {
+            Attribute::Property(name, PropertyValue::Bool(true))
+        }
+        Some(kind) if kind.property => Attribute::Property(name, PropertyValue::String(value)),
+        Some(kind) if kind.boolean => Attribute::Bool(name, true),
+        _ => Attribute::Custom(name, value),
+    }
+}
+
+fn nodes<Msg>(input: &str, nodes: Vec<Node>, out: &mut Vec<Html<Msg>>) -> Result<(), ParseError> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push(Html::Text(text)),
+            Node::Element(element) => {
+                let Some(spec) = spec::element_ignore_case(&element.name) else {
+                    let message = format!("unknown element `<{}>`", element.name);
+                    return Err(ParseError::new(input, element.offset, message));
+                };
+
+                let attributes = element
+                    .attributes
+                    .into_iter()
+                    .map(|(name, value)| attribute(spec, name, value))
+                    .collect();
+
+                let mut children = Vec::new();
+                self::nodes(input, element.children, &mut children)?;
+
+                out.push(Html::node_ns(
+                    spec.namespace,
+                    spec.name,
+                    None,
+                    attributes,
+                    children,
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses HTML into nodes that are rendered and diffed like the ones of `html!`. It fails on the
+/// first problem, e.g. a tag that is never closed or an element that isn't in the spec and isn't
+/// a custom element like `<my-dialog>`.
+///
+/// ```ignore
+/// let nodes = roko_html::parse::<Msg>("<p>Fish &amp; chips<br>on Fridays</p>")?;
+/// ```
+pub fn parse<Msg>(html: &str) -> Result<Vec<Html<Msg>>, ParseError> {
+    let (tree, errors) = tree(html);
+
+    if let Some(error) = errors.into_iter().next() {
+        return Err(error);
+    }
+
+    let mut out = Vec::new();
+    nodes(html, tree, &mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Markup of the parsed nodes, with the attributes as they are debugged.
+    fn markup(html: &str) -> String {
+        fn write(html: &Html<()>, out: &mut String) {
+            match html {
+                Html::Node(node) => {
+                    out.push('<');
+                    out.push_str(node.tag);
+                    for attribute in &node.attributes {
+                        out.push_str(&format!(" {attribute:?}"));
+                    }
+                    out.push('>');
+                    node.children.iter().for_each(|child| write(child, out));
+                    out.push_str(&format!("</{}>", node.tag));
+                }
+                Html::Text(text) => out.push_str(text),
+                other => panic!("unexpected node {other:?}"),
+            }
+        }
+
+        let mut out = String::new();
+        for node in parse(html).unwrap() {
+            write(&node, &mut out);
+        }
+        out
+    }
+
+    fn error(html: &str) -> String {
+        parse::<()>(html).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn reports_the_position_of_errors() {
+        assert_eq!(error("</span>"), "1:1: `</span>` doesn't close any tag");
+        assert!(error("<div>\n <span></div>").starts_with("2:8: expected `</span>`"));
+        assert!(error("<p>ü</b>").starts_with("1:5: "));
+        assert!(error("<div>\n<section>").starts_with("2:1: `<section>` is never closed"));
+        assert!(error("<blink>x</blink>").starts_with("1:1: unknown element"));
+    }
+
+    #[test]
+    fn decodes_character_references() {
+        assert_eq!(markup("Fish &amp; chips"), "Fish & chips");
+        assert_eq!(markup("&lt;&#39;&#x27;&gt;"), "<''>");
+        assert_eq!(markup("a & b &c"), "a & b &c");
+        assert_eq!(
+            markup(r#"<a href="?a=1&amp;b=2">x</a>"#),
+            r#"<a Custom("href", "?a=1&b=2")>x</a>"#
+        );
+        assert_eq!(markup("a &nope; b"), "a &nope; b");
+        assert_eq!(
+            markup("&#0;&#xD800;&#99999999999;"),
+            "\u{fffd}\u{fffd}\u{fffd}"
+        );
+        assert_eq!(markup("&#x1F600;&#;&#xZ;"), "😀&#;&#xZ;");
+    }
+
+    #[test]
+    fn parses_custom_elements() {
+        assert_eq!(
+            markup(r#"<my-card size="l"><p>x</p></my-card>"#),
+            r#"<my-card Custom("size", "l")><p>x</p></my-card>"#
+        );
+        assert!(error("<font-face></font-face>").starts_with("1:1: unknown element"));
+        assert_eq!(markup("<My-Card>x</My-Card>"), "<my-card>x</my-card>");
+    }
+
+    #[test]
+    fn closes_void_and_optional_elements() {
+        assert_eq!(markup("<p>a<br>b<hr>c"), "<p>a<br></br>b</p><hr></hr>c");
+        assert_eq!(
+            markup("<img src=a.png/>"),
+            r#"<img Custom("src", "a.png")></img>"#
+        );
+        assert_eq!(
+            markup("<ul><li>one<li>two</ul>"),
+            "<ul><li>one</li><li>two</li></ul>"
+        );
+    }
+
+    #[test]
+    fn parses_non_ascii_text() {
+        assert_eq!(markup("<p>élan</p>"), "<p>élan</p>");
+        assert_eq!(markup("日本語 <em>ü</em>"), "日本語 <em>ü</em>");
+    }
+}
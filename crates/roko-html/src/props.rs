@@ -0,0 +1,16 @@
+//! Typestate of the props builders generated by `#[component]`. Every required prop is a type
+//! parameter of the builder that starts as a marker named after the prop and becomes [Set] once
+//! it's given, so `build` only type checks when no required prop is missing.
+
+/// State of a required prop that was given.
+pub struct Set;
+
+/// Implemented by the state of the required props that were given. The markers of the missing
+/// ones don't implement it, so the error names the prop.
+#[diagnostic::on_unimplemented(
+    message = "missing required prop `{Self}`",
+    label = "the prop `{Self}` has to be given here"
+)]
+pub trait Provided {}
+
+impl Provided for Set {}
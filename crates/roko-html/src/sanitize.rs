@@ -0,0 +1,411 @@
+//! Sanitiser for HTML that doesn't come from a trusted source, e.g. user comments. The markup is
+//! parsed into [Html] nodes through an allowlist of tags, attributes and URL schemes, so nothing
+//! that isn't allowed reaches the DOM. Event attributes and `javascript:` URLs are never allowed,
+//! and since the nodes are created one by one the browser doesn't parse the markup again.
+//!
+//! ```ignore
+//! let comment = Sanitizer::default().sanitize::<Msg>(&post.body);
+//! let strict = Sanitizer::new().allow_tags(&["p", "em", "strong"]);
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use crate::parser::{self, Node};
+use crate::spec;
+use crate::Html;
+
+/// Elements that are removed with their content instead of being replaced by it.
+const REMOVED_WITH_CONTENT: &[&str] = &[
+    "embed", "frame", "frameset", "iframe", "math", "noembed", "noframes", "noscript", "object",
+    "script", "style", "svg", "template", "textarea", "title", "xmp",
+];
+
+/// Attributes that hold URLs, which are checked against the allowed schemes.
+const URL_ATTRIBUTES: &[&str] = &[
+    "action",
+    "background",
+    "cite",
+    "formaction",
+    "href",
+    "poster",
+    "src",
+    "xlink:href",
+];
+
+/// Allowlist of what survives sanitising. [Sanitizer::default] allows common formatting, links
+/// and images with `http`, `https` and `mailto` URLs, and [Sanitizer::new] allows nothing.
+#[derive(Debug, Clone)]
+pub struct Sanitizer {
+    tags: HashSet<String>,
+    /// Attributes of each tag, the ones under `*` are allowed on every tag.
+    attributes: HashMap<String, HashSet<String>>,
+    url_schemes: HashSet<String>,
+}
+
+impl Default for Sanitizer {
+    fn default() -> Self {
+        Self::new()
+            .allow_tags(&[
+                "a",
+                "abbr",
+                "b",
+                "blockquote",
+                "br",
+                "caption",
+                "cite",
+                "code",
+                "dd",
+                "del",
+                "details",
+                "dfn",
+                "div",
+                "dl",
+                "dt",
+                "em",
+                "figcaption",
+                "figure",
+                "h1",
+                "h2",
+                "h3",
+                "h4",
+                "h5",
+                "h6",
+                "hr",
+                "i",
+                "img",
+                "ins",
+                "kbd",
+                "li",
+                "mark",
+                "ol",
+                "p",
+                "pre",
+                "q",
+                "s",
+                "samp",
+                "small",
+                "span",
+                "strong",
+                "sub",
+                "summary",
+                "sup",
+                "table",
+                "tbody",
+                "td",
+                "tfoot",
+                "th",
+                "thead",
+                "time",
+                "tr",
+                "u",
+                "ul",
+            ])
+            .allow_attributes("*", &["dir", "lang", "title"])
+            .allow_attributes("a", &["href"])
+            .allow_attributes("img", &["alt", "height", "src", "width"])
+            .allow_attributes("ol", &["reversed", "start"])
+            .allow_attributes("td", &["colspan", "rowspan"])
+            .allow_attributes("th", &["colspan", "rowspan", "scope"])
+            .allow_attributes("time", &["datetime"])
+            .allow_url_schemes(&["http", "https", "mailto"])
+    }
+}
+
+impl Sanitizer {
+    /// A sanitiser that allows nothing, so only the text is kept.
+    pub fn new() -> Self {
+        Self {
+            tags: HashSet::new(),
+            attributes: HashMap::new(),
+            url_schemes: HashSet::new(),
+        }
+    }
+
+    pub fn allow_tags(mut self, tags: &[&str]) -> Self {
+        self.tags
+            .extend(tags.iter().map(|tag| tag.to_ascii_lowercase()));
+        self
+    }
+
+    /// Allows attributes on `tag`, or on every tag if it's `*`.
+    pub fn allow_attributes(mut self, tag: &str, attributes: &[&str]) -> Self {
+        self.attributes
+            .entry(tag.to_ascii_lowercase())
+            .or_default()
+            .extend(
+                attributes
+                    .iter()
+                    .map(|attribute| attribute.to_ascii_lowercase()),
+            );
+        self
+    }
+
+    pub fn allow_url_schemes(mut self, schemes: &[&str]) -> Self {
+        self.url_schemes
+            .extend(schemes.iter().map(|scheme| scheme.to_ascii_lowercase()));
+        self
+    }
+
+    pub fn remove_tags(mut self, tags: &[&str]) -> Self {
+        for tag in tags {
+            self.tags.remove(&tag.to_ascii_lowercase());
+        }
+        self
+    }
+
+    /// Parses `html` keeping only what is allowed. A tag that isn't allowed is replaced by its
+    /// content, except for the ones like `<script>` whose content isn't meant to be shown.
+    pub fn sanitize<Msg>(&self, html: &str) -> Vec<Html<Msg>> {
+        let mut out = Vec::new();
+        self.nodes(parser::tree(html).0, &mut out);
+        out
+    }
+
+    fn nodes<Msg>(&self, nodes: Vec<Node>, out: &mut Vec<Html<Msg>>) {
+        for node in nodes {
+            match node {
+                Node::Text(text) => out.push(Html::Text(text)),
+                Node::Element(element) => {
+                    if REMOVED_WITH_CONTENT.contains(&element.name.as_str()) {
+                        continue;
+                    }
+
+                    let spec = spec::element_ignore_case(&element.name)
+                        .filter(|_| self.tags.contains(&element.name));
+
+                    let Some(spec) = spec else {
+                        self.nodes(element.children, out);
+                        continue;
+                    };
+
+                    let attributes = element
+                        .attributes
+                        .into_iter()
+                        .filter(|(name, value)| self.allows_attribute(&element.name, name, value))
+                        .map(|(name, value)| parser::attribute(spec, name, value))
+                        .collect();
+
+                    let mut children = Vec::new();
+                    self.nodes(element.children, &mut children);
+
+                    out.push(Html::node_ns(
+                        spec.namespace,
+                        spec.name,
+                        None,
+                        attributes,
+                        children,
+                    ));
+                }
+            }
+        }
+    }
+
+    fn allows_attribute(&self, tag: &str, name: &str, value: &str) -> bool {
+        let allowed = [tag, "*"].iter().any(|tag| {
+            self.attributes
+                .get(*tag)
+                .is_some_and(|attributes| attributes.contains(name))
+        });
+
+        if !allowed || name.starts_with("on") {
+            return false;
+        }
+
+        if name == "srcset" {
+            return value.split(',').all(|candidate| {
+                let url = candidate.split_whitespace().next().unwrap_or_default();
+                self.allows_url(url)
+            });
+        }
+
+        !URL_ATTRIBUTES.contains(&name) || self.allows_url(value)
+    }
+
+    /// Whether the scheme of `url` is allowed. Relative URLs have no scheme, so they are always
+    /// allowed. Browsers ignore whitespace and control characters in a scheme, e.g. in
+    /// `java\tscript:`, so they are ignored here too.
+    fn allows_url(&self, url: &str) -> bool {
+        let url = url
+            .chars()
+            .filter(|ch| !ch.is_ascii_whitespace() && !ch.is_control())
+            .collect::<String>();
+
+        match url.split_once(':') {
+            Some((scheme, _)) if !scheme.contains(['/', '?', '#']) => {
+                self.url_schemes.contains(&scheme.to_ascii_lowercase())
+            }
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Attribute;
+
+    /// Markup of the sanitised nodes, with the attributes in their order.
+    fn sanitize(html: &str) -> String {
+        fn write(html: &Html<()>, out: &mut String) {
+            match html {
+                Html::Node(node) => {
+                    out.push('<');
+                    out.push_str(node.tag);
+                    for attribute in &node.attributes {
+                        match attribute {
+                            Attribute::Custom(name, value) => {
+                                out.push_str(&format!(" {name}={value:?}"))
+                            }
+                            other => out.push_str(&format!(" {other:?}")),
+                        }
+                    }
+                    out.push('>');
+                    node.children.iter().for_each(|child| write(child, out));
+                    out.push_str(&format!("</{}>", node.tag));
+                }
+                Html::Text(text) => out.push_str(text),
+                other => panic!("unexpected node {other:?}"),
+            }
+        }
+
+        let mut out = String::new();
+        for node in Sanitizer::default().sanitize(html) {
+            write(&node, &mut out);
+        }
+        out
+    }
+
+    #[test]
+    fn removes_javascript_urls() {
+        assert_eq!(
+            sanitize(r#"<a href="javascript:alert(1)">x</a>"#),
+            "<a>x</a>"
+        );
+        assert_eq!(
+            sanitize(r#"<a href="JavaScript:alert(1)">x</a>"#),
+            "<a>x</a>"
+        );
+        assert_eq!(
+            sanitize("<a href=\"java\tscript:alert(1)\">x</a>"),
+            "<a>x</a>"
+        );
+        assert_eq!(
+            sanitize(r#"<img src="data:image/png;base64,AAAA">"#),
+            "<img></img>"
+        );
+    }
+
+    #[test]
+    fn removes_urls_with_encoded_schemes() {
+        assert_eq!(
+            sanitize(r#"<a href="jav&#x61;script:alert(1)">x</a>"#),
+            "<a>x</a>"
+        );
+        assert_eq!(
+            sanitize(r#"<a href="jav&#97;script:alert(1)">x</a>"#),
+            "<a>x</a>"
+        );
+        assert_eq!(
+            sanitize(r#"<a href="javascript&colon;alert(1)">x</a>"#),
+            "<a>x</a>"
+        );
+        assert_eq!(
+            sanitize(r#"<a href="&#x09;javascript:alert(1)">x</a>"#),
+            "<a>x</a>"
+        );
+    }
+
+    #[test]
+    fn keeps_allowed_urls() {
+        assert_eq!(
+            sanitize(r#"<a href="https://example.com/?a=1&amp;b=2">x</a>"#),
+            r#"<a href="https://example.com/?a=1&b=2">x</a>"#
+        );
+        assert_eq!(
+            sanitize(r#"<a href="/posts/1#top">x</a>"#),
+            r#"<a href="/posts/1#top">x</a>"#
+        );
+    }
+
+    #[test]
+    fn removes_event_attributes() {
+        assert_eq!(sanitize(r#"<b onclick="alert(1)">x</b>"#), "<b>x</b>");
+        assert_eq!(
+            sanitize(r#"<img src=a.png OnError="alert(1)">"#),
+            r#"<img src="a.png"></img>"#
+        );
+
+        let sanitizer = Sanitizer::default().allow_attributes("*", &["onclick"]);
+        assert!(sanitizer
+            .sanitize::<()>(r#"<b onclick="alert(1)">x</b>"#)
+            .iter()
+            .all(|node| matches!(node, Html::Node(node) if node.attributes.is_empty())));
+    }
+
+    #[test]
+    fn removes_scripts_and_svg_with_their_content() {
+        assert_eq!(sanitize("a<script>alert(1)</script>b"), "ab");
+        assert_eq!(sanitize("a<SCRIPT>alert('</b>')</SCRIPT>b"), "ab");
+        assert_eq!(sanitize(r#"a<svg><script>alert(1)</script></svg>b"#), "ab");
+        assert_eq!(
+            sanitize(r#"<svg onload="alert(1)"><a href="x">y</a></svg>"#),
+            ""
+        );
+    }
+
+    #[test]
+    fn replaces_unknown_tags_with_their_content() {
+        assert_eq!(sanitize("<marquee><b>x</b></marquee>"), "<b>x</b>");
+    }
+
+    #[test]
+    fn keeps_quotes_in_attribute_values() {
+        assert_eq!(
+            sanitize(r#"<abbr title='say "hi"'>x</abbr>"#),
+            r#"<abbr title="say \"hi\"">x</abbr>"#
+        );
+        assert_eq!(
+            sanitize(r#"<abbr title="it&#39;s" lang=en>x</abbr>"#),
+            r#"<abbr title="it's" lang="en">x</abbr>"#
+        );
+        assert_eq!(
+            sanitize(r#"<abbr title="a" onmouseover="b">x</abbr>"#),
+            r#"<abbr title="a">x</abbr>"#
+        );
+    }
+
+    #[test]
+    fn keeps_non_ascii_text() {
+        assert_eq!(sanitize("<b>ünïcode</b>"), "<b>ünïcode</b>");
+        assert_eq!(sanitize("élan <i>日本語</i> ✓"), "élan <i>日本語</i> ✓");
+        assert_eq!(sanitize("ü<x>"), "ü");
+    }
+
+    #[test]
+    fn builds_attributes_like_parse() {
+        let sanitizer = Sanitizer::default().allow_attributes("*", &["class", "hidden"]);
+        let html = r#"<p class="a b" hidden>x</p>"#;
+
+        assert_eq!(
+            format!("{:?}", sanitizer.sanitize::<()>(html)),
+            format!("{:?}", crate::parse::<()>(html).unwrap())
+        );
+    }
+
+    #[test]
+    fn keeps_allowed_custom_elements() {
+        let sanitizer = Sanitizer::default().allow_tags(&["my-card"]);
+        let html = "<my-card><b>x</b></my-card><other-card>y</other-card>";
+
+        let markup = sanitizer
+            .sanitize::<()>(html)
+            .iter()
+            .map(|node| format!("{node:?}"))
+            .collect::<String>();
+        assert!(
+            markup.starts_with(r#"Node(Node { tag: "my-card""#),
+            "{markup}"
+        );
+        assert!(markup.ends_with(r#"Text("y")"#), "{markup}");
+    }
+}
@@ -0,0 +1,345 @@
+//! Elements and attributes of HTML, SVG and MathML, read from `spec/elements.spec`. `html!` checks
+//! elements and their attributes against it, and `roko_dom` generates its element constructors and
+//! attribute helpers from it. `data-*` attributes are allowed on every element and aren't listed.
+//! Custom elements, whose names have a `-`, aren't listed either.
+
+use std::sync::{Mutex, OnceLock};
+
+use crate::Namespace;
+
+/// Path of the spec file, so build scripts can rerun when it changes.
+pub const PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/spec/elements.spec");
+
+const SOURCE: &str = include_str!("../spec/elements.spec");
+
+#[derive(Debug, Clone)]
+pub struct AttributeSpec {
+    pub name: &'static str,
+    /// The presence of the attribute is the value, e.g. `disabled`.
+    pub boolean: bool,
+    /// The attribute is only the initial state, so it's set as a property, e.g. `value`.
+    pub property: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ElementSpec {
+    pub name: &'static str,
+    pub namespace: Namespace,
+    /// Attributes of this element, without the global ones.
+    pub attributes: Vec<AttributeSpec>,
+}
+
+#[derive(Debug, Default)]
+pub struct Spec {
+    pub global: Vec<AttributeSpec>,
+    pub aria: Vec<AttributeSpec>,
+    /// Attributes of every element of a namespace, e.g. the presentation attributes of SVG.
+    pub shared: Vec<(Namespace, Vec<AttributeSpec>)>,
+    pub elements: Vec<ElementSpec>,
+}
+
+impl Spec {
+    pub fn element(&self, name: &str) -> Option<&ElementSpec> {
+        self.elements.iter().find(|element| element.name == name)
+    }
+
+    pub fn shared(&self, namespace: Namespace) -> &[AttributeSpec] {
+        self.shared
+            .iter()
+            .find(|(shared, _)| *shared == namespace)
+            .map_or(&[], |(_, attributes)| attributes)
+    }
+
+    /// Element `name` ignoring case, for markup parsed at runtime where names are lowercased,
+    /// e.g. `clippath` for `clipPath`.
+    pub fn element_ignore_case(&self, name: &str) -> Option<&ElementSpec> {
+        self.elements
+            .iter()
+            .find(|element| element.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Attribute `name` of `element`, if the element allows it.
+    pub fn attribute<'a>(
+        &'a self,
+        element: &'a ElementSpec,
+        name: &str,
+    ) -> Option<&'a AttributeSpec> {
+        self.attributes(element)
+            .find(|attribute| attribute.name == name)
+    }
+
+    /// Attribute `name` of `element` ignoring case, e.g. `viewbox` for `viewBox`.
+    pub fn attribute_ignore_case<'a>(
+        &'a self,
+        element: &'a ElementSpec,
+        name: &str,
+    ) -> Option<&'a AttributeSpec> {
+        self.attributes(element)
+            .find(|attribute| attribute.name.eq_ignore_ascii_case(name))
+    }
+
+    fn attributes<'a>(
+        &'a self,
+        element: &'a ElementSpec,
+    ) -> impl Iterator<Item = &'a AttributeSpec> {
+        element
+            .attributes
+            .iter()
+            .chain(self.shared(element.namespace))
+            .chain(&self.global)
+            .chain(&self.aria)
+    }
+}
+
+/// Names with a `-` that the specs already use, so they can't be custom elements.
+const RESERVED_NAMES: &[&str] = &[
+    "annotation-xml",
+    "color-profile",
+    "font-face",
+    "font-face-format",
+    "font-face-name",
+    "font-face-src",
+    "font-face-uri",
+    "missing-glyph",
+];
+
+/// Whether `name` is a valid name for a custom element, e.g. `my-dialog`: it starts with a
+/// lowercase letter and has a `-`.
+pub fn is_custom_element(name: &str) -> bool {
+    name.starts_with(|ch: char| ch.is_ascii_lowercase())
+        && name.contains('-')
+        && name.chars().all(|ch| {
+            ch.is_ascii_lowercase()
+                || ch.is_ascii_digit()
+                || matches!(ch, '-' | '.' | '_')
+                || !ch.is_ascii()
+        })
+        && !RESERVED_NAMES.contains(&name)
+}
+
+/// Spec of a custom element, which only has the global attributes. Elements have a static name,
+/// so the spec of each name is made once and kept.
+pub fn custom_element(name: &str) -> Option<&'static ElementSpec> {
+    static CUSTOM: Mutex<Vec<&'static ElementSpec>> = Mutex::new(Vec::new());
+
+    if !is_custom_element(name) {
+        return None;
+    }
+
+    let mut custom = CUSTOM.lock().unwrap_or_else(|error| error.into_inner());
+
+    if let Some(element) = custom.iter().find(|element| element.name == name) {
+        return Some(element);
+    }
+
+    let element = Box::leak(Box::new(ElementSpec {
+        name: Box::leak(name.into()),
+        namespace: Namespace::Html,
+        attributes: Vec::new(),
+    }));
+    custom.push(element);
+    Some(element)
+}
+
+/// Element `name` of the spec ignoring case, or the custom element with that name.
+pub fn element_ignore_case(name: &str) -> Option<&'static ElementSpec> {
+    spec()
+        .element_ignore_case(name)
+        .or_else(|| custom_element(name))
+}
+
+pub fn is_data_attribute(name: &str) -> bool {
+    name.strip_prefix("data-")
+        .is_some_and(|rest| !rest.is_empty())
+}
+
+fn attribute(name: &'static str) -> AttributeSpec {
+    let (name, property) = match name.strip_suffix('!') {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+
+    let (name, boolean) = match name.strip_suffix('?') {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+
+    AttributeSpec {
+        name,
+        boolean,
+        property,
+    }
+}
+
+fn invalid(number: usize, message: &str) -> ! {
+    panic!("{PATH}:{}: {message}", number + 1)
+}
+
+enum Section {
+    Global,
+    Aria,
+    Namespace(Namespace),
+}
+
+fn parse(source: &'static str) -> Spec {
+    let mut spec = Spec::default();
+    let mut section = None;
+    // Whether the last entry of a namespace was `*`, which indented lines continue.
+    let mut last_shared = false;
+
+    for (number, line) in source.lines().enumerate() {
+        let content = line.split('#').next().unwrap_or_default();
+
+        if content.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(name) = content.trim().strip_prefix('[') {
+            section = Some(match name.strip_suffix(']') {
+                Some("global") => Section::Global,
+                Some("aria") => Section::Aria,
+                Some("html") => Section::Namespace(Namespace::Html),
+                Some("svg") => Section::Namespace(Namespace::Svg),
+                Some("mathml") => Section::Namespace(Namespace::MathMl),
+                _ => invalid(number, "unknown section"),
+            });
+            continue;
+        }
+
+        let attributes = match &section {
+            None => invalid(number, "expected a section"),
+            Some(Section::Global) => &mut spec.global,
+            Some(Section::Aria) => &mut spec.aria,
+            Some(Section::Namespace(_)) if content.starts_with(char::is_whitespace) => {
+                let last = if last_shared {
+                    spec.shared.last_mut().map(|(_, attributes)| attributes)
+                } else {
+                    spec.elements
+                        .last_mut()
+                        .map(|element| &mut element.attributes)
+                };
+
+                last.unwrap_or_else(|| invalid(number, "indented line without an element"))
+            }
+            Some(Section::Namespace(namespace)) => {
+                let (name, rest) = content.split_once(':').unwrap_or((content, ""));
+                let attributes = rest.split_whitespace().map(attribute).collect();
+
+                last_shared = name.trim() == "*";
+
+                if last_shared {
+                    spec.shared.push((*namespace, attributes));
+                } else {
+                    spec.elements.push(ElementSpec {
+                        name: name.trim(),
+                        namespace: *namespace,
+                        attributes,
+                    });
+                }
+                continue;
+            }
+        };
+
+        attributes.extend(content.split_whitespace().map(attribute));
+    }
+
+    spec
+}
+
+/// The parsed spec, it's read once.
+pub fn spec() -> &'static Spec {
+    static SPEC: OnceLock<Spec> = OnceLock::new();
+    SPEC.get_or_init(|| parse(SOURCE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "
+# A comment.
+[global]
+id hidden?
+[aria]
+role
+[html]
+input: checked?! value! # The value is a property.
+    type
+[svg]
+*: fill
+circle: r
+";
+
+    fn names(attributes: &[AttributeSpec]) -> Vec<&str> {
+        attributes.iter().map(|attribute| attribute.name).collect()
+    }
+
+    #[test]
+    fn parses_sections_and_continued_lines() {
+        let spec = parse(SOURCE);
+
+        assert_eq!(names(&spec.global), ["id", "hidden"]);
+        assert_eq!(names(&spec.aria), ["role"]);
+        assert_eq!(names(spec.shared(Namespace::Svg)), ["fill"]);
+
+        let input = spec.element("input").unwrap();
+        assert_eq!(input.namespace, Namespace::Html);
+        assert_eq!(names(&input.attributes), ["checked", "value", "type"]);
+        assert_eq!(spec.element("circle").unwrap().namespace, Namespace::Svg);
+    }
+
+    #[test]
+    fn parses_boolean_and_property_attributes() {
+        let spec = parse(SOURCE);
+        let input = spec.element("input").unwrap();
+        let kind = |name| {
+            let attribute = spec.attribute(input, name).unwrap();
+            (attribute.boolean, attribute.property)
+        };
+
+        assert_eq!(kind("checked"), (true, true));
+        assert_eq!(kind("value"), (false, true));
+        assert_eq!(kind("type"), (false, false));
+        assert_eq!(kind("hidden"), (true, false));
+        assert!(spec.attribute(input, "fill").is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "elements.spec:2: unknown section")]
+    fn rejects_unknown_sections() {
+        parse("\n[css]\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "elements.spec:1: expected a section")]
+    fn rejects_entries_without_a_section() {
+        parse("div: id");
+    }
+
+    #[test]
+    fn reads_the_spec_file() {
+        let spec = spec();
+
+        assert_eq!(
+            spec.element_ignore_case("clippath").unwrap().name,
+            "clipPath"
+        );
+        assert!(spec
+            .attribute(spec.element("a").unwrap(), "aria-label")
+            .is_some());
+    }
+
+    #[test]
+    fn accepts_custom_elements() {
+        assert!(is_custom_element("my-dialog"));
+        assert!(is_custom_element("x-é.1_"));
+        assert!(!is_custom_element("dialog"));
+        assert!(!is_custom_element("My-dialog"));
+        assert!(!is_custom_element("1-dialog"));
+        assert!(!is_custom_element("font-face"));
+
+        let element = custom_element("my-dialog").unwrap();
+        assert!(std::ptr::eq(element, custom_element("my-dialog").unwrap()));
+        assert_eq!(element_ignore_case("My-Dialog").map(|e| e.name), None);
+    }
+}
@@ -0,0 +1,253 @@
+//! Two-way bindings of form elements, e.g. `<input bind:value={model.name => Msg::SetName} />`.
+//! A binding expands to the property that shows the field and the event that sends it back,
+//! parsed with `FromStr` into the type that the message takes, so a number field that doesn't
+//! parse sends nothing.
+//!
+//! - `bind:value` sets `value` and listens to `input`.
+//! - `bind:checked` sets `checked` and listens to `change` with the state of the checkbox.
+//! - `bind:group` is for radios, which are checked when their `value` attribute is the field, and
+//!   for selects, which select the option with the field as value. Both listen to `change`.
+
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{Expr, Token};
+
+/// The arrow between the field and the message isn't Rust code, so the value of every binding is
+/// wrapped in a macro call before syn-rsx parses it, e.g. `{bind!(model.name => Msg::SetName)}`.
+pub fn prepare(tokens: TokenStream) -> TokenStream {
+    let mut tokens = tokens.into_iter().collect::<Vec<_>>();
+
+    for i in 0..tokens.len() {
+        let is_binding = matches!(
+            &tokens[i..],
+            [TokenTree::Ident(bind), TokenTree::Punct(colon), TokenTree::Ident(_), TokenTree::Punct(eq), TokenTree::Group(group), ..]
+                if bind == "bind"
+                    && colon.as_char() == ':'
+                    && eq.as_char() == '='
+                    && group.delimiter() == Delimiter::Brace
+        );
+
+        if !is_binding {
+            continue;
+        }
+
+        let TokenTree::Group(group) = &tokens[i + 4] else {
+            unreachable!()
+        };
+
+        let content = group.stream();
+        let mut wrapped = Group::new(Delimiter::Brace, quote! { bind!(#content) });
+        wrapped.set_span(group.span());
+        tokens[i + 4] = TokenTree::Group(wrapped);
+    }
+
+    tokens.into_iter().collect()
+}
+
+struct Binding {
+    field: Expr,
+    msg: Expr,
+}
+
+impl Parse for Binding {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let field = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let msg = input.parse()?;
+        Ok(Self { field, msg })
+    }
+}
+
+fn binding(attr: &syn_rsx::NodeAttribute) -> syn::Result<Binding> {
+    let usage = || {
+        syn::Error::new(
+            attr.key.span(),
+            format!(
+                "expected a field and a message, e.g. `{}={{model.field => Msg::SetField}}`",
+                attr.key
+            ),
+        )
+    };
+
+    let value = attr.value.as_ref().ok_or_else(usage)?;
+    let value: Expr = syn::parse2(value.as_ref().to_token_stream())?;
+
+    let Expr::Block(block) = value else {
+        return Err(usage());
+    };
+
+    match block.block.stmts.as_slice() {
+        [syn::Stmt::Macro(mac)] => mac.mac.parse_body(),
+        [syn::Stmt::Expr(Expr::Macro(mac), None)] => mac.mac.parse_body(),
+        _ => Err(usage()),
+    }
+}
+
+/// Value of a plain attribute of the element, e.g. the `value` of a radio.
+fn attribute(el: &syn_rsx::NodeElement, name: &str) -> Option<TokenStream> {
+    el.attributes.iter().find_map(|attr| match attr {
+        syn_rsx::Node::Attribute(attr) if attr.key.to_string() == name => attr
+            .value
+            .as_ref()
+            .map(|value| value.as_ref().to_token_stream()),
+        _ => None,
+    })
+}
+
+/// Expands a `bind:` attribute of `el` into its attributes.
+pub fn expand(
+    el: &syn_rsx::NodeElement,
+    attr: &syn_rsx::NodeAttribute,
+) -> syn::Result<Vec<TokenStream>> {
+    let kind = attr.key.to_string();
+    let Binding { field, msg } = binding(attr)?;
+
+    let parsed = quote! {
+        {
+            let msg = #msg;
+            roko_html::Listener::new(move |event: roko_html::InputEvent| {
+                ::std::str::FromStr::from_str(&event.value).ok().map(msg)
+            })
+        }
+    };
+
+    let shown =
+        quote! { roko_html::PropertyValue::from(::std::string::ToString::to_string(&(#field))) };
+
+    match kind.as_str() {
+        "bind:value" => Ok(vec![
+            quote! { roko_html::Attribute::Property("value".to_string(), #shown) },
+            quote! { roko_html::Attribute::OnInput(#parsed) },
+        ]),
+        "bind:checked" => Ok(vec![
+            quote! { roko_html::Attribute::Property("checked".to_string(), roko_html::PropertyValue::from(#field)) },
+            quote! {
+                roko_html::Attribute::OnChange({
+                    let msg = #msg;
+                    roko_html::Listener::new(move |event: roko_html::InputEvent| Some(msg(event.checked)))
+                })
+            },
+        ]),
+        "bind:group" if el.name.to_string() == "select" => Ok(vec![
+            quote! { roko_html::Attribute::Property("value".to_string(), #shown) },
+            quote! { roko_html::Attribute::OnChange(#parsed) },
+        ]),
+        "bind:group" => {
+            let Some(value) = attribute(el, "value") else {
+                return Err(syn::Error::new(
+                    attr.key.span(),
+                    "`bind:group` on a radio needs its `value` attribute",
+                ));
+            };
+
+            Ok(vec![
+                quote! {
+                    roko_html::Attribute::Property(
+                        "checked".to_string(),
+                        roko_html::PropertyValue::from(::std::string::ToString::to_string(&(#field)) == ::std::string::ToString::to_string(&(#value))),
+                    )
+                },
+                quote! { roko_html::Attribute::OnChange(#parsed) },
+            ])
+        }
+        _ => Err(syn::Error::new(
+            attr.key.span(),
+            format!(
+                "unknown binding `{kind}`, expected `bind:value`, `bind:checked` or `bind:group`"
+            ),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(tokens: TokenStream) -> Result<String, String> {
+        crate::html_nodes(tokens)
+            .map(|tokens| tokens.to_string())
+            .map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn wraps_the_values_of_bindings() {
+        let tokens = prepare(quote! { <input bind:value={model.age => Msg::SetAge} title={x} /> });
+
+        assert_eq!(
+            tokens.to_string(),
+            "< input bind : value = { bind ! (model . age => Msg :: SetAge) } title = { x } / >"
+        );
+    }
+
+    #[test]
+    fn binds_values() {
+        let html = expand(quote! { <input bind:value={model.age => Msg::SetAge} /> }).unwrap();
+
+        assert!(
+            html.contains("Property (\"value\" . to_string ()"),
+            "{html}"
+        );
+        assert!(
+            html.contains("ToString :: to_string (& (model . age))"),
+            "{html}"
+        );
+        assert!(html.contains("Attribute :: OnInput"), "{html}");
+        assert!(
+            html.contains("FromStr :: from_str (& event . value)"),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn binds_checkboxes() {
+        let html =
+            expand(quote! { <input type="checkbox" bind:checked={model.done => Msg::SetDone} /> })
+                .unwrap();
+
+        assert!(
+            html.contains("Property (\"checked\" . to_string () , roko_html :: PropertyValue :: from (model . done))"),
+            "{html}"
+        );
+        assert!(html.contains("Some (msg (event . checked))"), "{html}");
+    }
+
+    #[test]
+    fn binds_groups() {
+        let radio = expand(
+            quote! { <input type="radio" value="s" bind:group={model.size => Msg::SetSize} /> },
+        )
+        .unwrap();
+        let select =
+            expand(quote! { <select bind:group={model.size => Msg::SetSize}></select> }).unwrap();
+
+        assert!(radio.contains("Property (\"checked\""), "{radio}");
+        assert!(radio.contains("Attribute :: OnChange"), "{radio}");
+        assert!(select.contains("Property (\"value\""), "{select}");
+        assert!(select.contains("Attribute :: OnChange"), "{select}");
+    }
+
+    #[test]
+    fn rejects_invalid_bindings() {
+        assert_eq!(
+            expand(quote! { <input bind:value={model.age} /> }),
+            Err("expected `=>`".to_string())
+        );
+        assert_eq!(
+            expand(quote! { <input bind:value /> }),
+            Err(
+                "expected a field and a message, e.g. `bind:value={model.field => Msg::SetField}`"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            expand(quote! { <input type="radio" bind:group={model.size => Msg::SetSize} /> }),
+            Err("`bind:group` on a radio needs its `value` attribute".to_string())
+        );
+        assert_eq!(
+            expand(quote! { <input bind:files={model.files => Msg::SetFiles} /> }),
+            Err("unknown binding `bind:files`, expected `bind:value`, `bind:checked` or `bind:group`".to_string())
+        );
+    }
+}
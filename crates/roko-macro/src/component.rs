@@ -0,0 +1,385 @@
+//! Expansion of `#[component]`. The parameters of the function become the fields of a props
+//! struct with a builder, and the function takes that struct instead. `html!` builds the props
+//! from the attributes of `<Card title="..." />`, so a missing or unknown prop is a type error.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::spanned::Spanned;
+use syn::{
+    parse_quote, Expr, FnArg, GenericArgument, GenericParam, Generics, Ident, ItemFn, Pat,
+    PathArguments, Token, Type,
+};
+
+enum Kind {
+    /// Has to be given, it's tracked by the typestate of the builder.
+    Required,
+    /// Uses the expression when it's not given.
+    Default(Expr),
+    /// An `Option<T>`, the setter receives a `T` and it's `None` when not given.
+    Optional(Type),
+}
+
+struct Prop {
+    ident: Ident,
+    pat: Pat,
+    ty: Type,
+    kind: Kind,
+    into: bool,
+}
+
+/// Returns `T` if the type is `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+
+    match args.args.first() {
+        Some(GenericArgument::Type(ty)) if args.args.len() == 1 => Some(ty),
+        _ => None,
+    }
+}
+
+/// Whether the type is `Children<Msg>`, which is the type of slots.
+fn is_children(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.path.segments.last().is_some_and(|segment| segment.ident == "Children"))
+}
+
+fn prop(arg: &mut FnArg) -> syn::Result<Prop> {
+    let FnArg::Typed(arg) = arg else {
+        return Err(syn::Error::new(arg.span(), "components can't take `self`"));
+    };
+
+    let Pat::Ident(pat) = &*arg.pat else {
+        return Err(syn::Error::new(
+            arg.pat.span(),
+            "the parameters of a component have to be named, since they are its props",
+        ));
+    };
+
+    let ident = pat.ident.clone();
+    let mut default = None;
+    let mut into = false;
+
+    for attr in arg.attrs.iter().filter(|attr| attr.path().is_ident("prop")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("default") {
+                default = Some(if meta.input.peek(Token![=]) {
+                    meta.value()?.parse()?
+                } else {
+                    parse_quote!(::std::default::Default::default())
+                });
+                Ok(())
+            } else if meta.path.is_ident("into") {
+                into = true;
+                Ok(())
+            } else {
+                Err(meta.error("unknown prop option, expected `default` or `into`"))
+            }
+        })?;
+    }
+
+    arg.attrs.retain(|attr| !attr.path().is_ident("prop"));
+
+    let ty = (*arg.ty).clone();
+
+    // Children and slots are always optional, an element without them gives an empty list.
+    let kind = if let Some(default) = default {
+        Kind::Default(default)
+    } else if let Some(inner) = option_inner(&ty) {
+        Kind::Optional(inner.clone())
+    } else if ident == "children" || is_children(&ty) {
+        Kind::Default(parse_quote!(::std::default::Default::default()))
+    } else {
+        Kind::Required
+    };
+
+    Ok(Prop {
+        ident,
+        pat: (*arg.pat).clone(),
+        ty,
+        kind,
+        into,
+    })
+}
+
+/// The generic parameters as arguments, e.g. `'a, Msg` for `<'a, Msg: Clone>`.
+fn generic_args(generics: &Generics) -> Vec<TokenStream> {
+    generics
+        .params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Lifetime(param) => param.lifetime.to_token_stream(),
+            GenericParam::Type(param) => param.ident.to_token_stream(),
+            GenericParam::Const(param) => param.ident.to_token_stream(),
+        })
+        .collect()
+}
+
+/// Zero sized type that uses every generic parameter, so they don't have to appear in the props.
+fn marker(generics: &Generics) -> TokenStream {
+    let uses = generics.params.iter().filter_map(|param| match param {
+        GenericParam::Lifetime(param) => {
+            let lifetime = &param.lifetime;
+            Some(quote! { &#lifetime () })
+        }
+        GenericParam::Type(param) => {
+            let ident = &param.ident;
+            Some(quote! { fn() -> #ident })
+        }
+        GenericParam::Const(_) => None,
+    });
+
+    quote! { ::std::marker::PhantomData<(#(#uses,)*)> }
+}
+
+pub fn expand(mut item: ItemFn) -> syn::Result<TokenStream> {
+    let props = item
+        .sig
+        .inputs
+        .iter_mut()
+        .map(prop)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let vis = &item.vis;
+    let name = &item.sig.ident;
+    let generics = &item.sig.generics;
+    let params = generics.params.iter().collect::<Vec<_>>();
+    let where_clause = &generics.where_clause;
+    let args = generic_args(generics);
+    let marker = marker(generics);
+
+    let props_ident = format_ident!("{}Props", name);
+    let builder_ident = format_ident!("{}PropsBuilder", name);
+    let states_mod = format_ident!("__{}_props", name.to_string().to_lowercase());
+
+    let idents = props.iter().map(|prop| &prop.ident).collect::<Vec<_>>();
+    let pats = props.iter().map(|prop| &prop.pat);
+    let types = props.iter().map(|prop| &prop.ty);
+
+    let required = props
+        .iter()
+        .filter(|prop| matches!(prop.kind, Kind::Required))
+        .map(|prop| &prop.ident)
+        .collect::<Vec<_>>();
+
+    let states = required
+        .iter()
+        .map(|ident| format_ident!("__{}", ident))
+        .collect::<Vec<_>>();
+
+    let builder_fields = props.iter().map(|prop| {
+        let ident = &prop.ident;
+        let ty = &prop.ty;
+        match prop.kind {
+            Kind::Optional(_) => quote! { #ident: #ty },
+            _ => quote! { #ident: ::std::option::Option<#ty> },
+        }
+    });
+
+    let setters = props.iter().map(|prop| {
+        let ident = &prop.ident;
+        let doc = format!("Sets the prop `{ident}`.");
+
+        let (value_ty, value) = match &prop.kind {
+            Kind::Optional(inner) if prop.into => (
+                quote! { impl ::std::convert::Into<#inner> },
+                quote! { ::std::option::Option::Some(value.into()) },
+            ),
+            Kind::Optional(inner) => (quote! { #inner }, quote! { ::std::option::Option::Some(value) }),
+            _ if prop.into => {
+                let ty = &prop.ty;
+                (
+                    quote! { impl ::std::convert::Into<#ty> },
+                    quote! { ::std::option::Option::Some(value.into()) },
+                )
+            }
+            _ => {
+                let ty = &prop.ty;
+                (quote! { #ty }, quote! { ::std::option::Option::Some(value) })
+            }
+        };
+
+        if let Some(index) = required.iter().position(|required| *required == ident) {
+            // Setting a required prop changes its state, so the builder is rebuilt field by field.
+            let others = states
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != index)
+                .map(|(_, state)| state);
+
+            let before = states.iter().enumerate().map(|(i, state)| {
+                if i == index {
+                    quote! { #states_mod::#ident }
+                } else {
+                    quote! { #state }
+                }
+            });
+
+            let after = states.iter().enumerate().map(|(i, state)| {
+                if i == index {
+                    quote! { ::roko_html::props::Set }
+                } else {
+                    quote! { #state }
+                }
+            });
+
+            let rest = idents.iter().filter(|other| **other != ident);
+
+            quote! {
+                #[allow(non_camel_case_types)]
+                impl<#(#params,)* #(#others),*> #builder_ident<#(#args,)* #(#before),*> #where_clause {
+                    #[doc = #doc]
+                    #vis fn #ident(self, value: #value_ty) -> #builder_ident<#(#args,)* #(#after),*> {
+                        #builder_ident {
+                            #ident: #value,
+                            #(#rest: self.#rest,)*
+                            _marker: ::std::marker::PhantomData,
+                            _states: ::std::marker::PhantomData,
+                        }
+                    }
+                }
+            }
+        } else {
+            quote! {
+                #[allow(non_camel_case_types)]
+                impl<#(#params,)* #(#states),*> #builder_ident<#(#args,)* #(#states),*> #where_clause {
+                    #[doc = #doc]
+                    #vis fn #ident(mut self, value: #value_ty) -> Self {
+                        self.#ident = #value;
+                        self
+                    }
+                }
+            }
+        }
+    });
+
+    let build_fields = props.iter().map(|prop| {
+        let ident = &prop.ident;
+        match &prop.kind {
+            Kind::Required => quote! { #ident: self.#ident.unwrap() },
+            Kind::Default(default) => quote! { #ident: self.#ident.unwrap_or_else(|| #default) },
+            Kind::Optional(_) => quote! { #ident: self.#ident },
+        }
+    });
+
+    let props_doc = format!("Props of [`{name}`], built by `html!` from its attributes.");
+
+    let mut function = item.clone();
+    function.sig.inputs = parse_quote! { props: #props_ident<#(#args),*> };
+    let block = &item.block;
+    function.block = parse_quote! {{
+        #[allow(non_shorthand_field_patterns)]
+        let #props_ident { #(#idents: #pats,)* .. } = props;
+        #block
+    }};
+
+    Ok(quote! {
+        #[allow(non_snake_case)]
+        #function
+
+        #[doc = #props_doc]
+        #vis struct #props_ident<#(#params),*> #where_clause {
+            #(#vis #idents: #types,)*
+            _marker: #marker,
+        }
+
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        #vis mod #states_mod {
+            #(pub struct #required;)*
+        }
+
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        #vis struct #builder_ident<#(#params,)* #(#states),*> #where_clause {
+            #(#builder_fields,)*
+            _marker: #marker,
+            _states: ::std::marker::PhantomData<(#(#states,)*)>,
+        }
+
+        impl<#(#params),*> #props_ident<#(#args),*> #where_clause {
+            /// Starts building the props, every required prop has to be set before `build`.
+            #vis fn builder() -> #builder_ident<#(#args,)* #(#states_mod::#required),*> {
+                #builder_ident {
+                    #(#idents: ::std::default::Default::default(),)*
+                    _marker: ::std::marker::PhantomData,
+                    _states: ::std::marker::PhantomData,
+                }
+            }
+        }
+
+        #(#setters)*
+
+        #[allow(non_camel_case_types)]
+        impl<#(#params,)* #(#states),*> #builder_ident<#(#args,)* #(#states),*> #where_clause {
+            /// Finishes the props. The bounds are on the method so a missing prop is reported by
+            /// its name.
+            #vis fn build(self) -> #props_ident<#(#args),*>
+            where
+                #(#states: ::roko_html::props::Provided,)*
+            {
+                #props_ident {
+                    #(#build_fields,)*
+                    _marker: ::std::marker::PhantomData,
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(item: ItemFn) -> String {
+        expand(item).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn rejects_self() {
+        let item = parse_quote! { fn Card(&self) -> Html<Msg> { html! {} } };
+        assert_eq!(error(item), "components can't take `self`");
+    }
+
+    #[test]
+    fn rejects_unnamed_parameters() {
+        let item = parse_quote! { fn Card((a, b): (u8, u8)) -> Html<Msg> { html! {} } };
+        assert_eq!(
+            error(item),
+            "the parameters of a component have to be named, since they are its props"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_prop_options() {
+        let item =
+            parse_quote! { fn Card(#[prop(optional)] title: String) -> Html<Msg> { html! {} } };
+        assert_eq!(
+            error(item),
+            "unknown prop option, expected `default` or `into`"
+        );
+    }
+
+    #[test]
+    fn tracks_only_required_props() {
+        let item = parse_quote! {
+            fn Card(title: String, subtitle: Option<String>, #[prop(default)] count: u32, children: Children<Msg>) -> Html<Msg> {
+                html! {}
+            }
+        };
+        let tokens = expand(item).unwrap().to_string();
+
+        assert!(tokens.contains("pub struct title ;"));
+        assert!(!tokens.contains("pub struct subtitle ;"));
+        assert!(!tokens.contains("pub struct count ;"));
+        assert!(!tokens.contains("pub struct children ;"));
+        assert!(tokens.contains("__title : :: roko_html :: props :: Provided"));
+    }
+}
@@ -0,0 +1,269 @@
+//! `class` and `style` of an element, together with the `class:name={cond}` and
+//! `style:property={value}` directives, are merged into a single attribute. Literal parts are
+//! split when the macro expands, so only the dynamic ones are handled at runtime.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Expr, ExprLit, Lit, Token};
+
+use crate::{attribute_value, local};
+
+pub fn is_class(key: &str) -> bool {
+    key == "class" || key.starts_with("class:")
+}
+
+pub fn is_style(key: &str) -> bool {
+    key == "style" || key.starts_with("style:")
+}
+
+fn missing_value(attr: &syn_rsx::NodeAttribute) -> syn::Error {
+    syn::Error::new(attr.key.span(), format!("`{}` needs a value", attr.key))
+}
+
+/// Builds an `Attribute::Class` from the class attributes of an element.
+pub fn class(attrs: &[&syn_rsx::NodeAttribute]) -> syn::Result<TokenStream> {
+    let class = local("class");
+    let mut parts = Vec::new();
+
+    for attr in attrs {
+        let key = attr.key.to_string();
+
+        if let Some(name) = key.strip_prefix("class:") {
+            let cond = attribute_value(attr)?.ok_or_else(|| missing_value(attr))?;
+            parts.push(quote! {
+                if #cond {
+                    #class.push(#name.to_string());
+                }
+            });
+            continue;
+        }
+
+        match attribute_value(attr)? {
+            Some(Expr::Lit(ExprLit {
+                lit: Lit::Str(lit), ..
+            })) => {
+                let names = lit.value();
+                let names = names.split_whitespace();
+                parts.push(quote! { #(#class.push(#names.to_string());)* });
+            }
+            Some(value) => parts.push(quote! { #class.extend(roko_html::class_names(&(#value))); }),
+            None => return Err(missing_value(attr)),
+        }
+    }
+
+    Ok(quote! {
+        roko_html::Attribute::Class({
+            let mut #class = Vec::new();
+            #(#parts)*
+            #class
+        })
+    })
+}
+
+/// Builds an `Attribute::Style` from the style attributes of an element.
+pub fn style(attrs: &[&syn_rsx::NodeAttribute]) -> syn::Result<TokenStream> {
+    let style = local("style");
+    let mut parts = Vec::new();
+
+    for attr in attrs {
+        let key = attr.key.to_string();
+
+        if let Some(name) = key.strip_prefix("style:") {
+            let value = attribute_value(attr)?.ok_or_else(|| missing_value(attr))?;
+            parts.push(quote! {
+                #style.push((#name.to_string(), ::std::string::ToString::to_string(&(#value))));
+            });
+            continue;
+        }
+
+        match attribute_value(attr)? {
+            Some(Expr::Lit(ExprLit {
+                lit: Lit::Str(lit), ..
+            })) => {
+                let (names, values): (Vec<_>, Vec<_>) = roko_html::style_declarations(&lit.value())
+                    .into_iter()
+                    .unzip();
+                parts.push(quote! { #(#style.push((#names.to_string(), #values.to_string()));)* });
+            }
+            Some(value) => {
+                parts.push(quote! { #style.extend(roko_html::style_declarations(&(#value))); })
+            }
+            None => return Err(missing_value(attr)),
+        }
+    }
+
+    Ok(quote! {
+        roko_html::Attribute::Style({
+            let mut #style = Vec::new();
+            #(#parts)*
+            #style
+        })
+    })
+}
+
+/// An argument of `classes!`, either a class or `"class" => cond`.
+struct ClassItem {
+    class: Expr,
+    cond: Option<Expr>,
+}
+
+impl Parse for ClassItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let class = input.parse()?;
+
+        let cond = if input.parse::<Option<Token![=>]>>()?.is_some() {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        Ok(Self { class, cond })
+    }
+}
+
+pub fn classes(input: ParseStream) -> syn::Result<TokenStream> {
+    let classes = local("classes");
+    let items = Punctuated::<ClassItem, Token![,]>::parse_terminated(input)?;
+
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+
+    // Consecutive literals without a condition are joined into one string.
+    for item in items {
+        match (&item.class, &item.cond) {
+            (
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(lit), ..
+                }),
+                None,
+            ) => {
+                for name in lit.value().split_whitespace() {
+                    literal.push_str(name);
+                    literal.push(' ');
+                }
+            }
+            (class, cond) => {
+                if !literal.is_empty() {
+                    let names = std::mem::take(&mut literal);
+                    parts.push(quote! { #classes.push_str(#names); });
+                }
+
+                let push = quote! {
+                    #classes.push_str(&::std::string::ToString::to_string(&(#class)));
+                    #classes.push(' ');
+                };
+
+                parts.push(match cond {
+                    Some(cond) => quote! { if #cond { #push } },
+                    None => push,
+                });
+            }
+        }
+    }
+
+    if !literal.is_empty() {
+        parts.push(quote! { #classes.push_str(#literal); });
+    }
+
+    Ok(quote! {
+        {
+            let mut #classes = ::std::string::String::new();
+            #(#parts)*
+            #classes.truncate(#classes.trim_end().len());
+            #classes
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse::Parser;
+
+    fn expand(tokens: TokenStream) -> Result<String, String> {
+        crate::html_nodes(tokens)
+            .map(|tokens| tokens.to_string())
+            .map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn merges_classes_into_one_attribute() {
+        let html =
+            expand(quote! { <div class="card big" title="x" class:active={on} class={extra} /> })
+                .unwrap();
+
+        assert_eq!(html.matches("Attribute :: Class").count(), 1, "{html}");
+        assert!(
+            html.contains(
+                "class . push (\"card\" . to_string ()) ; class . push (\"big\" . to_string ()) ;"
+            ),
+            "{html}"
+        );
+        assert!(
+            html.contains("if on { class . push (\"active\" . to_string ()) ; }"),
+            "{html}"
+        );
+        assert!(
+            html.contains("roko_html :: class_names (& (extra))"),
+            "{html}"
+        );
+        // The merged attribute stays where the first class was.
+        assert!(
+            html.find("Attribute :: Class") < html.find("\"title\""),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn merges_styles_into_one_attribute() {
+        let html =
+            expand(quote! { <div style="color: red; margin: 0" style:width={px} /> }).unwrap();
+
+        assert_eq!(html.matches("Attribute :: Style").count(), 1, "{html}");
+        assert!(
+            html.contains("style . push ((\"color\" . to_string () , \"red\" . to_string ())) ; style . push ((\"margin\" . to_string () , \"0\" . to_string ())) ;"),
+            "{html}"
+        );
+        assert!(
+            html.contains("style . push ((\"width\" . to_string () , :: std :: string :: ToString :: to_string (& (px))))"),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn rejects_directives_without_a_value() {
+        assert_eq!(
+            expand(quote! { <div class:active /> }),
+            Err("`class:active` needs a value".to_string())
+        );
+        assert_eq!(
+            expand(quote! { <div style:width /> }),
+            Err("`style:width` needs a value".to_string())
+        );
+    }
+
+    #[test]
+    fn joins_literals_of_classes() {
+        let classes = classes
+            .parse2(quote! { "button", "big  round", "active" => selected, size.class(), "x" })
+            .unwrap()
+            .to_string();
+
+        assert!(
+            classes.contains("classes . push_str (\"button big round \") ;"),
+            "{classes}"
+        );
+        assert!(classes.contains("if selected {"), "{classes}");
+        assert!(
+            classes.contains("to_string (& (size . class ()))"),
+            "{classes}"
+        );
+        assert!(
+            classes.contains("classes . push_str (\"x \") ;"),
+            "{classes}"
+        );
+    }
+}
@@ -0,0 +1,45 @@
+//! Files read by macros like `include_markdown!`. Paths are relative to the file of the call, like
+//! the ones of `include_str!`, and the expansion includes the bytes of the file so the crate is
+//! rebuilt when it changes.
+
+use std::path::PathBuf;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::LitStr;
+
+pub struct File {
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+impl File {
+    /// Item that makes cargo track the file, it has to be placed in the expansion.
+    pub fn track(&self) -> TokenStream {
+        let path = self.path.to_string_lossy();
+        quote! { const _: &[u8] = include_bytes!(#path); }
+    }
+}
+
+pub fn read(path: &LitStr) -> syn::Result<File> {
+    let base = proc_macro::Span::call_site()
+        .local_file()
+        .and_then(|file| file.parent().map(ToOwned::to_owned))
+        .or_else(|| std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from))
+        .unwrap_or_default();
+
+    let error = |error: std::io::Error| {
+        syn::Error::new(
+            path.span(),
+            format!("couldn't read `{}`: {error}", path.value()),
+        )
+    };
+
+    let full = base.join(path.value()).canonicalize().map_err(error)?;
+    let contents = std::fs::read_to_string(&full).map_err(error)?;
+
+    Ok(File {
+        path: full,
+        contents,
+    })
+}
@@ -1,16 +1,41 @@
 #![feature(proc_macro_span)]
+#![feature(proc_macro_tracked_env)]
 
 use proc_macro::TokenStream;
 use quote::{quote, ToTokens};
-use syn::{Expr, ItemFn};
+use roko_html::spec::{AttributeSpec, ElementSpec};
+use syn::parse::{ParseStream, Parser};
+use syn::spanned::Spanned;
+use syn::{braced, Expr, ItemFn, Pat, Token};
+use syn_rsx::ParserConfig;
+
+mod bind;
+mod component;
+mod directives;
+mod include;
+mod lower;
+mod markdown;
+mod style;
+mod tags;
+mod template;
+mod text;
 
 #[proc_macro_attribute]
 #[allow(clippy::redundant_clone)]
 pub fn cmd(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    let mut parsed: ItemFn = syn::parse(item).unwrap();
+    let parsed = syn::parse_macro_input!(item as ItemFn);
+
+    cmd_item(parsed)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
+fn cmd_item(mut parsed: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
     if parsed.sig.asyncness.is_none() {
-        panic!("cmd must be async");
+        return Err(syn::Error::new(
+            parsed.sig.fn_token.span,
+            "commands have to be `async` functions",
+        ));
     }
 
     let name = parsed.sig.ident;
@@ -22,176 +47,1251 @@ pub fn cmd(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
     let new_name = parsed.sig.ident.clone();
 
-    let args_call = args.clone();
-
-    let args_call = args_call
+    let args_call = args
         .iter()
         .map(|arg| match arg {
-            syn::FnArg::Typed(pat) => {
-                let pat = pat.pat.clone();
-                match &*pat {
-                    syn::Pat::Ident(name) => name.ident.clone(),
-                    _ => todo!("pattern matching"),
-                }
-            }
-            _ => todo!(),
+            syn::FnArg::Typed(pat) => match &*pat.pat {
+                syn::Pat::Ident(name) => Ok(name.ident.clone()),
+                pat => Err(syn::Error::new(
+                    pat.span(),
+                    "arguments of commands have to be plain names, destructure them in the body",
+                )),
+            },
+            syn::FnArg::Receiver(receiver) => Err(syn::Error::new(
+                receiver.span(),
+                "commands can't take `self`",
+            )),
         })
-        .collect::<Vec<_>>();
+        .collect::<syn::Result<Vec<_>>>()?;
 
-    quote! {
+    Ok(quote! {
         pub fn #name #gen (#args) -> Box<dyn std::future::Future<Output = Option<Msg>> + std::marker::Unpin> {
             #parsed
             Box::new(Box::pin(#new_name(
                 #(#args_call),*
             )))
         }
-    }.into()
+    })
+}
+
+/// Turns a function into a component with typed props. Every parameter is a prop: `Option<T>`
+/// parameters, `children` and slots are optional, `#[prop(default)]` or `#[prop(default = expr)]` give
+/// a default and `#[prop(into)]` makes the prop accept anything that converts into its type.
+///
+/// ```
+/// # use roko_dom::elements::*;
+/// # use roko_html::{Children, Html};
+/// # use roko_macro::{component, html};
+/// #[component]
+/// pub fn Card<Msg: 'static>(
+///     title: String,
+///     #[prop(default = 3)] stars: usize,
+///     children: Children<Msg>,
+/// ) -> Html<Msg> {
+///     html! { <div class="card"><h2>{title}</h2>{stars}{Html::Fragment(children)}</div> }
+/// }
+///
+/// let card: Html<()> = html! { <Card title="Hi"><p>"..."</p></Card> };
+/// ```
+///
+/// A prop that is missing or unknown doesn't compile:
+///
+/// ```compile_fail,E0277
+/// # use roko_dom::elements::*;
+/// # use roko_html::Html;
+/// # use roko_macro::{component, html};
+/// #[component]
+/// pub fn Card<Msg: 'static>(title: String) -> Html<Msg> {
+///     html! { <h2>{title}</h2> }
+/// }
+///
+/// let card: Html<()> = html! { <Card /> };
+/// ```
+///
+/// ```compile_fail,E0599
+/// # use roko_dom::elements::*;
+/// # use roko_html::Html;
+/// # use roko_macro::{component, html};
+/// #[component]
+/// pub fn Card<Msg: 'static>(title: String) -> Html<Msg> {
+///     html! { <h2>{title}</h2> }
+/// }
+///
+/// let card: Html<()> = html! { <Card title="Hi" subtitle="typo" /> };
+/// ```
+///
+/// Other `Children<Msg>` parameters are named slots, filled by `<slot:name>` elements:
+///
+/// ```
+/// # use roko_dom::elements::*;
+/// # use roko_html::{Children, Html};
+/// # use roko_macro::{component, html};
+/// #[derive(Clone, PartialEq)]
+/// enum Msg {
+///     Delete,
+/// }
+///
+/// #[component]
+/// pub fn Modal<Msg: 'static>(header: Children<Msg>, footer: Children<Msg>, children: Children<Msg>) -> Html<Msg> {
+///     html! { <div class="modal">{Html::Fragment(header)}{Html::Fragment(children)}{Html::Fragment(footer)}</div> }
+/// }
+///
+/// let modal: Html<Msg> = html! {
+///     <Modal>
+///         <slot:header><h2>"Delete post?"</h2></slot:header>
+///         <p>"This can't be undone."</p>
+///         <slot:footer><button onclick={Msg::Delete}>"Delete"</button></slot:footer>
+///     </Modal>
+/// };
+/// ```
+///
+/// A slot that the component doesn't have doesn't compile:
+///
+/// ```compile_fail,E0599
+/// # use roko_dom::elements::*;
+/// # use roko_html::{Children, Html};
+/// # use roko_macro::{component, html};
+/// #[component]
+/// pub fn Modal<Msg: 'static>(header: Children<Msg>, children: Children<Msg>) -> Html<Msg> {
+///     html! { <div class="modal">{Html::Fragment(header)}{Html::Fragment(children)}</div> }
+/// }
+///
+/// let modal: Html<()> = html! { <Modal><slot:footer>"Close"</slot:footer></Modal> };
+/// ```
+#[proc_macro_attribute]
+pub fn component(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let item = syn::parse_macro_input!(item as ItemFn);
+
+    component::expand(item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Builds a class string from class names and `"name" => cond` pairs, e.g.
+/// `classes!["button", "active" => selected, size.class()]`. Consecutive literals are joined when
+/// the macro expands.
+///
+/// ```
+/// # use roko_macro::classes;
+/// let (selected, size) = (true, "big");
+/// assert_eq!(classes!["button", "round", "active" => selected, "hidden" => false, size], "button round active big");
+/// ```
+#[proc_macro]
+pub fn classes(item: TokenStream) -> TokenStream {
+    directives::classes
+        .parse(item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Renders markdown while compiling, e.g. `markdown!("Hello, *world*")`, giving an `Html<Msg>`.
+/// A struct after it is filled with the front-matter, see [include_markdown!].
+///
+/// ```
+/// # use roko_html::Html;
+/// # use roko_macro::markdown;
+/// struct Post {
+///     title: String,
+///     tags: Vec<String>,
+/// }
+///
+/// let (post, body): (Post, Html<()>) =
+///     markdown!("---\ntitle: Hello\ntags: [rust, wasm]\n---\nHello, *world*", Post);
+/// assert_eq!(post.title, "Hello");
+/// assert_eq!(post.tags, ["rust", "wasm"]);
+/// ```
+#[proc_macro]
+pub fn markdown(item: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(item as markdown::Input);
+
+    markdown::markdown(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Renders a markdown file while compiling, with a path relative to the current file like the
+/// one of `include_str!`. Editing the file rebuilds the crate. With a struct after the path, e.g.
+/// `include_markdown!("posts/intro.md", Post)`, it gives a `(Post, Html<Msg>)` whose fields are
+/// taken from the front-matter:
+///
+/// ```md
+/// ---
+/// title: Writing a virtual DOM
+/// tags: [rust, wasm]
+/// ---
+/// ```
+#[proc_macro]
+pub fn include_markdown(item: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(item as markdown::Input);
+
+    markdown::include_markdown(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Scoped styles in SCSS, compiled while compiling and bundled into `static/style.css` by
+/// `roko-build`. It gives the class that scopes them, e.g.
+/// `let card = style! { padding: 20px; &:hover { color: red; } };` to use in
+/// `html! { <div class={card}>...</div> }`. The styles can also be in a string.
+#[proc_macro]
+pub fn style(item: TokenStream) -> TokenStream {
+    style::expand(item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Another name for [style!].
+#[proc_macro]
+pub fn css(item: TokenStream) -> TokenStream {
+    style(item)
 }
 
 fn get_attribute_from_attrs(
     attrs: &[syn_rsx::Node],
     name: &'static str,
-) -> Option<proc_macro2::TokenStream> {
-    attrs.iter().find_map(|attr| {
+) -> syn::Result<Option<proc_macro2::TokenStream>> {
+    for attr in attrs {
         if let syn_rsx::Node::Attribute(attr) = attr {
-            get_attribute(attr, name)
-        } else {
-            None
+            if let Some(value) = get_attribute(attr, name)? {
+                return Ok(Some(value));
+            }
         }
-    })
+    }
+
+    Ok(None)
 }
 
+/// Value of one of the attributes that are arguments of the element function instead of
+/// attributes of the node, which can't be left empty.
 fn get_attribute(
-    attrs: &syn_rsx::NodeAttribute,
+    attr: &syn_rsx::NodeAttribute,
     name: &'static str,
-) -> Option<proc_macro2::TokenStream> {
-    if attrs.key.to_string() == name {
-        let value = attrs.value.as_ref().unwrap();
+) -> syn::Result<Option<proc_macro2::TokenStream>> {
+    if attr.key.to_string() != name {
+        return Ok(None);
+    }
 
-        let value: syn::Expr = syn::parse(value.as_ref().to_token_stream().into()).unwrap();
+    let Some(value) = attribute_value(attr)? else {
+        return Err(syn::Error::new(
+            attr.key.span(),
+            format!("`{name}` needs a value, e.g. `{name}={{value}}`"),
+        ));
+    };
 
-        let result = match value {
-            Expr::Block(block) => {
-                let stmt = &block.block.stmts[0];
-                quote! { #stmt }
-            }
-            _ => {
-                quote! { #value.to_string() }
+    if let Expr::Lit(syn::ExprLit {
+        lit: syn::Lit::Str(lit),
+        ..
+    }) = &value
+    {
+        if lit.value().is_empty() {
+            return Err(syn::Error::new(
+                lit.span(),
+                format!("`{name}` can't be empty"),
+            ));
+        }
+    }
+
+    let result = match value {
+        Expr::Lit(_) => quote! { #value.to_string() },
+        _ => quote! { #value },
+    };
+
+    Ok(Some(result))
+}
+
+/// Parses the value of an attribute. A block with a single expression is unwrapped, so
+/// `title={name}` gives `name`, and an empty block is an error.
+fn attribute_value(attr: &syn_rsx::NodeAttribute) -> syn::Result<Option<Expr>> {
+    let Some(value) = &attr.value else {
+        return Ok(None);
+    };
+
+    let tokens = value.as_ref().to_token_stream();
+    let value: Expr = syn::parse2(tokens.clone())?;
+
+    match value {
+        Expr::Block(block) if block.block.stmts.is_empty() => Err(syn::Error::new_spanned(
+            tokens,
+            format!("`{}` can't be empty", attr.key),
+        )),
+        Expr::Block(mut block) if block.block.stmts.len() == 1 => match block.block.stmts.pop() {
+            Some(syn::Stmt::Expr(expr, None)) => Ok(Some(expr)),
+            stmt => Ok(Some(Expr::Block(syn::ExprBlock {
+                block: syn::Block {
+                    stmts: stmt.into_iter().collect(),
+                    ..block.block
+                },
+                ..block
+            }))),
+        },
+        value => Ok(Some(value)),
+    }
+}
+
+/// Path of a typed component, which is a tag whose last segment is capitalized, e.g. `<Card>` or
+/// `<home_card::Card>`.
+fn component_path(el: &syn_rsx::NodeElement) -> Option<syn::Path> {
+    let path: syn::Path = syn::parse2(el.name.to_token_stream()).ok()?;
+    let last = path.segments.last()?.ident.to_string();
+
+    last.starts_with(char::is_uppercase).then_some(path)
+}
+
+/// Builds the props of a typed component with its attributes and children, so the compiler
+/// checks them against the `#[component]` function.
+fn transform_component(
+    el: &syn_rsx::NodeElement,
+    tag: syn::Path,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut props = tag.clone();
+    let last = props.segments.last_mut().unwrap();
+    last.ident = quote::format_ident!("{}Props", last.ident, span = last.ident.span());
+
+    let mut setters = Vec::new();
+
+    for attr in &el.attributes {
+        // A spread is forwarded to the `attrs` prop of the component.
+        let attr = match attr {
+            syn_rsx::Node::Attribute(attr) => attr,
+            syn_rsx::Node::Block(block) => {
+                let attrs = spread(block)?;
+                setters.push(quote! { .attrs(#attrs) });
+                continue;
             }
+            _ => continue,
         };
 
-        Some(result)
+        let name: syn::Ident = syn::parse2(attr.key.to_token_stream())
+            .map_err(|_| syn::Error::new(attr.key.span(), "props have to be identifiers"))?;
+
+        let value = match attribute_value(attr)? {
+            Some(Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(lit),
+                ..
+            })) => quote! { ::std::string::ToString::to_string(#lit) },
+            Some(value) => quote! { #value },
+            None => quote! { true },
+        };
+
+        setters.push(quote! { .#name(#value) });
+    }
+
+    let mut children = Vec::new();
+    let mut slots: Vec<syn::Ident> = Vec::new();
+
+    for child in &el.children {
+        let Some((slot, slot_el)) = slot(child)? else {
+            children.push(transform(child)?);
+            continue;
+        };
+
+        if slots.contains(&slot) {
+            return Err(syn::Error::new(
+                slot.span(),
+                format!("the slot `{slot}` is given twice"),
+            ));
+        }
+
+        let nodes = transform_all(&slot_el.children)?;
+        setters.push(quote! { .#slot(vec![#(#nodes),*]) });
+        slots.push(slot);
+    }
+
+    let children = if children.is_empty() {
+        quote! {}
     } else {
-        None
+        quote! { .children(vec![#(#children),*]) }
+    };
+
+    let build = quote::quote_spanned! { el.name.span() => .build() };
+
+    Ok(quote! {
+        #tag(#props::builder() #(#setters)* #children #build)
+    })
+}
+
+/// Properties that have no attribute, so they aren't in the spec. Attributes that are only the
+/// initial state of an element, like `value`, are marked in the spec and also set as properties,
+/// so the view keeps controlling them after the user interacts with the element. Any other
+/// property can be set with the `prop:` prefix.
+const PROPERTIES: &[&str] = &[
+    "indeterminate",
+    "scrollLeft",
+    "scrollTop",
+    "selectedIndex",
+    "valueAsNumber",
+];
+
+/// Attributes that `html!` handles itself, on top of the ones in the spec.
+const ROKO_ATTRIBUTES: &[&str] = &[
+    "children",
+    "key",
+    "model",
+    "onchange",
+    "onclick",
+    "oninput",
+    "onmount",
+    "onunmount",
+];
+
+/// Prefixes of directives, e.g. `class:active={cond}`.
+const DIRECTIVES: &[&str] = &["bind:", "class:", "prop:", "style:"];
+
+/// Spec of an element written as a tag, e.g. `<div>` or a custom element like `<my-dialog>`. Paths
+/// like `<navbar::navbar>` call functions that aren't in the spec, so they give `None`.
+fn element_spec(el: &syn_rsx::NodeElement) -> syn::Result<Option<&'static ElementSpec>> {
+    let name = el.name.to_string();
+
+    if name.contains("::") {
+        return Ok(None);
+    }
+
+    let element = roko_html::spec::spec()
+        .element(&name)
+        .or_else(|| roko_html::spec::custom_element(&name));
+
+    match element {
+        Some(element) => Ok(Some(element)),
+        None if name.contains('-') => Err(syn::Error::new(
+            el.name.span(),
+            format!("`<{name}>` isn't a valid custom element name, it has to be lowercase, start with a letter and not be reserved like `font-face`"),
+        )),
+        None => Err(syn::Error::new(
+            el.name.span(),
+            format!(
+                "unknown element `<{name}>`, functions that render elements are called with their path, e.g. `<module::{name}>`"
+            ),
+        )),
     }
 }
 
-fn transform(node: &syn_rsx::Node) -> proc_macro2::TokenStream {
-    match node {
-        syn_rsx::Node::Element(el) => {
-            let tag = el.name.to_token_stream();
+/// Spec of an attribute of `element`. Functions aren't in the spec, so their attributes give
+/// `None` and are passed as they are written.
+fn attribute_spec(
+    element: Option<&'static ElementSpec>,
+    name: &str,
+) -> Option<&'static AttributeSpec> {
+    element.and_then(|element| roko_html::spec::spec().attribute(element, name))
+}
 
-            let attrs = el.attributes.iter().map(transform);
-            let children = el.children.iter().map(transform);
+/// Checks that the element allows an attribute. `data-*` attributes are allowed everywhere.
+fn check_attribute(element: &ElementSpec, attr: &syn_rsx::NodeAttribute) -> syn::Result<()> {
+    let name = attr.key.to_string();
 
-            let mut children = quote! { vec![#(#children),*] };
+    let allowed = ROKO_ATTRIBUTES.contains(&name.as_str())
+        || PROPERTIES.contains(&name.as_str())
+        || DIRECTIVES.iter().any(|prefix| name.starts_with(prefix))
+        || roko_html::spec::is_data_attribute(&name)
+        || roko_html::spec::spec().attribute(element, &name).is_some();
 
-            let model = get_attribute_from_attrs(&el.attributes, "model");
+    if allowed {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            attr.key.span(),
+            format!(
+                "`{name}` isn't an attribute of `<{}>`, custom attributes have to start with `data-`",
+                element.name
+            ),
+        ))
+    }
+}
 
-            if let Some(child) = get_attribute_from_attrs(&el.attributes, "children") {
-                children = child;
+/// An entry of the attribute list of an element.
+enum AttrItem {
+    Attr(proc_macro2::TokenStream),
+    Spread(Box<Expr>),
+}
+
+/// Parses `{..attrs}` inside of a tag.
+fn spread(block: &syn_rsx::NodeBlock) -> syn::Result<Expr> {
+    let tokens = block.value.as_ref().to_token_stream();
+
+    if let Ok(Expr::Block(block)) = syn::parse2(tokens.clone()) {
+        if let [syn::Stmt::Expr(Expr::Range(range), None)] = block.block.stmts.as_slice() {
+            if let (None, Some(end), syn::RangeLimits::HalfOpen(_)) =
+                (&range.start, &range.end, &range.limits)
+            {
+                return Ok((**end).clone());
             }
+        }
+    }
 
-            let key = if let Some(attr) = get_attribute_from_attrs(&el.attributes, "key") {
-                quote! { Some(#attr) }
-            } else {
-                quote! { None }
+    Err(syn::Error::new_spanned(
+        tokens,
+        "blocks inside of a tag have to spread attributes, e.g. `{..attrs}`",
+    ))
+}
+
+/// Builds the attribute list of an element. With spreads the attributes are merged by
+/// [roko_html::merge_attributes], so the result doesn't depend on what the spread contains.
+fn attr_list(attrs: Vec<AttrItem>) -> proc_macro2::TokenStream {
+    let plain = attrs
+        .iter()
+        .map(|attr| match attr {
+            AttrItem::Attr(attr) => Some(attr),
+            AttrItem::Spread(_) => None,
+        })
+        .collect::<Option<Vec<_>>>();
+
+    if let Some(attrs) = plain {
+        return quote! { vec![#(#attrs),*] };
+    }
+
+    // The list has a mixed site span, so a spread of a variable called `attrs` still works.
+    let list = local("attrs");
+
+    let attrs = attrs.iter().map(|attr| match attr {
+        AttrItem::Attr(attr) => quote! { #list.push(#attr); },
+        AttrItem::Spread(spread) => quote! { #list.extend(#spread); },
+    });
+
+    quote! {
+        roko_html::merge_attributes({
+            let mut #list = Vec::new();
+            #(#attrs)*
+            #list
+        })
+    }
+}
+
+/// Variable of the generated code that the expressions of the user can't see.
+pub(crate) fn local(name: &str) -> syn::Ident {
+    syn::Ident::new(name, proc_macro2::Span::mixed_site())
+}
+
+/// Name of a slot element like `<slot:header>`, which gives the nodes of the `header` field of a
+/// component.
+fn slot(node: &syn_rsx::Node) -> syn::Result<Option<(syn::Ident, &syn_rsx::NodeElement)>> {
+    let syn_rsx::Node::Element(el) = node else {
+        return Ok(None);
+    };
+
+    let name = el.name.to_string();
+
+    let Some(slot) = name.strip_prefix("slot:") else {
+        return Ok(None);
+    };
+
+    match syn::parse_str::<syn::Ident>(slot) {
+        Ok(slot) => Ok(Some((
+            syn::Ident::new(&slot.to_string(), el.name.span()),
+            el,
+        ))),
+        Err(_) => Err(syn::Error::new(
+            el.name.span(),
+            "slot names have to be identifiers, e.g. `<slot:header>`",
+        )),
+    }
+}
+
+/// Expands an attribute of `element`, which is `None` for the elements of functions.
+fn transform_attribute(
+    element: Option<&'static ElementSpec>,
+    attr: &syn_rsx::NodeAttribute,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let name = attr.key.to_string();
+    let kind = attribute_spec(element, &name);
+
+    let mut needs_rc = false;
+    let mut is_custom = false;
+    let mut ignore = false;
+
+    let constructor = match name.as_str() {
+        "onclick" => {
+            needs_rc = true;
+            quote! {OnClick}
+        }
+        "onmount" => {
+            needs_rc = true;
+            quote! {OnMount}
+        }
+        "onunmount" => {
+            needs_rc = true;
+            quote! {OnUnmount}
+        }
+        "oninput" | "onchange" => {
+            let Some(value) = attribute_value(attr)? else {
+                return Err(syn::Error::new(
+                    attr.key.span(),
+                    format!("`{name}` needs a function that receives the value"),
+                ));
             };
 
-            if let Some(model) = model {
-                quote! {
-                    #tag(#model, #key, vec![#(#attrs),*], #children)
-                }
+            let constructor = if name == "oninput" {
+                quote! {OnInput}
             } else {
-                quote! {
-                    #tag(#key, vec![#(#attrs),*], #children)
-                }
-            }
+                quote! {OnChange}
+            };
+
+            return Ok(quote! {
+                roko_html::Attribute::#constructor({
+                    let on_value = #value;
+                    roko_html::Listener::new(move |event: roko_html::InputEvent| Some(on_value(event.value)))
+                })
+            });
+        }
+        "model" | "children" => {
+            ignore = true;
+            quote! {None}
         }
-        syn_rsx::Node::Attribute(attr) => {
-            let name = attr.key.to_string();
+        _ => {
+            is_custom = true;
+            quote! {Custom}
+        }
+    };
 
-            let mut needs_rc = false;
-            let mut is_custom = false;
-            let mut ignore = false;
+    let Some(value) = attribute_value(attr)? else {
+        // Events need the message they send, so `<button onclick>` isn't a boolean attribute.
+        if needs_rc {
+            return Err(syn::Error::new(
+                attr.key.span(),
+                format!("`{name}` needs the message to send, e.g. `{name}={{message}}`"),
+            ));
+        }
 
-            let constructor = match name.as_str() {
-                "onclick" => {
-                    needs_rc = true;
-                    quote! {OnClick}
-                }
-                "onmount" => {
-                    needs_rc = true;
-                    quote! {OnMount}
-                }
-                "onunmount" => {
-                    needs_rc = true;
-                    quote! {OnUnmount}
-                }
-                "model" | "children" => {
-                    ignore = true;
-                    quote! {None}
-                }
-                _ => {
-                    is_custom = true;
-                    quote! {Custom}
-                }
-            };
+        // A valueless attribute like `<input disabled />` is a boolean that is always on.
+        return Ok(quote! { roko_html::Attribute::Bool(#name.to_string(), true) });
+    };
+
+    let is_string = matches!(
+        value,
+        Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(_),
+            ..
+        })
+    );
+
+    let result = match value {
+        Expr::Lit(_) => quote! { #value.to_string() },
+        _ => quote! { #value },
+    };
+
+    // The property would be set to the string, which is truthy even for `selected="no"`.
+    if is_custom && is_string && kind.is_some_and(|kind| kind.boolean && kind.property) {
+        return Err(syn::Error::new_spanned(
+            value,
+            format!("`{name}` is a boolean, write `{name}` or `{name}={{cond}}`"),
+        ));
+    }
+
+    if needs_rc {
+        Ok(quote! { roko_html::Attribute::#constructor(roko_html::Handler::message(#result)) })
+    } else if let Some(property) = name.strip_prefix("prop:") {
+        Ok(
+            quote! { roko_html::Attribute::Property(#property.to_string(), roko_html::PropertyValue::from(#value)) },
+        )
+    } else if is_custom
+        && element.is_some()
+        && (PROPERTIES.contains(&name.as_str()) || kind.is_some_and(|kind| kind.property))
+    {
+        Ok(
+            quote! { roko_html::Attribute::Property(#name.to_string(), roko_html::PropertyValue::from(#value)) },
+        )
+    } else if is_custom && !is_string && kind.is_some_and(|kind| kind.boolean) {
+        Ok(quote! { roko_html::Attribute::Bool(#name.to_string(), #value) })
+    } else if is_custom {
+        Ok(quote! { roko_html::Attribute::Custom(#name.to_string(), #result.to_string()) })
+    } else if ignore {
+        Ok(quote! {})
+    } else {
+        Ok(quote! { roko_html::Attribute::#constructor(#result) })
+    }
+}
 
-            if let Some(value) = &attr.value {
-                // Idk how to pattern match on syn::expr::Expr so I did that
-                let value: syn::Expr = syn::parse(value.as_ref().to_token_stream().into()).unwrap();
+fn transform_all(nodes: &[syn_rsx::Node]) -> syn::Result<Vec<proc_macro2::TokenStream>> {
+    nodes.iter().map(transform).collect()
+}
+
+fn transform(node: &syn_rsx::Node) -> syn::Result<proc_macro2::TokenStream> {
+    match node {
+        syn_rsx::Node::Element(el) => {
+            if let Some(tag) = component_path(el) {
+                return transform_component(el, tag);
+            }
+
+            // Slots are taken by the component they belong to, so any other is misplaced.
+            if slot(node)?.is_some() {
+                return Err(syn::Error::new(
+                    el.name.span(),
+                    "slots have to be direct children of a `#[component]`",
+                ));
+            }
+
+            let element = element_spec(el)?;
+            let mut tag = el.name.to_token_stream();
+
+            // Tags that are keywords, like the `<use>` of SVG, are functions with raw names.
+            let name = el.name.to_string();
+            // Custom elements take any attribute, they are defined by their script.
+            let custom = roko_html::spec::is_custom_element(&name);
+            if syn::parse_str::<syn::Ident>(&name).is_err() && !name.contains(['-', ':']) {
+                tag = proc_macro2::Ident::new_raw(&name, el.name.span()).to_token_stream();
+            }
+
+            let mut attrs = Vec::new();
+            let mut class = (None, Vec::new());
+            let mut style = (None, Vec::new());
 
-                let result = match value {
-                    Expr::Block(block) => {
-                        let stmt = &block.block.stmts[0];
-                        quote! { #stmt }
+            for attr in &el.attributes {
+                if let (Some(element), syn_rsx::Node::Attribute(attr), false) =
+                    (element, attr, custom)
+                {
+                    check_attribute(element, attr)?;
+                }
+
+                match attr {
+                    syn_rsx::Node::Block(block) => {
+                        attrs.push(AttrItem::Spread(Box::new(spread(block)?)))
+                    }
+                    syn_rsx::Node::Attribute(binding)
+                        if binding.key.to_string().starts_with("bind:") =>
+                    {
+                        let expanded = bind::expand(el, binding)?;
+                        attrs.extend(expanded.into_iter().map(AttrItem::Attr))
                     }
-                    _ => {
-                        quote! { #value.to_string() }
+                    // Class and style attributes are merged at the position of the first one.
+                    syn_rsx::Node::Attribute(attr)
+                        if directives::is_class(&attr.key.to_string()) =>
+                    {
+                        class.0.get_or_insert(attrs.len());
+                        class.1.push(attr);
                     }
-                };
-
-                if needs_rc {
-                    quote! { roko_html::Attribute::#constructor(std::sync::Arc::new(#result)) }
-                } else if is_custom {
-                    quote! { roko_html::Attribute::Custom(#name.to_string(), #result.to_string()) }
-                } else if ignore {
-                    quote! {}
-                } else {
-                    quote! { roko_html::Attribute::#constructor(#result) }
+                    syn_rsx::Node::Attribute(attr)
+                        if directives::is_style(&attr.key.to_string()) =>
+                    {
+                        style.0.get_or_insert(attrs.len());
+                        style.1.push(attr);
+                    }
+                    syn_rsx::Node::Attribute(attr) => {
+                        attrs.push(AttrItem::Attr(transform_attribute(element, attr)?))
+                    }
+                    attr => attrs.push(AttrItem::Attr(transform(attr)?)),
                 }
+            }
+
+            let mut merged = Vec::new();
+
+            if let (Some(at), class) = class {
+                merged.push((at, directives::class(&class)?));
+            }
+
+            if let (Some(at), style) = style {
+                merged.push((at, directives::style(&style)?));
+            }
+
+            merged.sort_by_key(|(at, _)| *at);
+
+            for (at, attr) in merged.into_iter().rev() {
+                attrs.insert(at, AttrItem::Attr(attr));
+            }
+
+            // `model` and `children` are arguments of the element function, so they give no tokens.
+            attrs.retain(|attr| !matches!(attr, AttrItem::Attr(attr) if attr.is_empty()));
+
+            let attrs = attr_list(attrs);
+            let children = transform_all(&el.children)?;
+
+            let mut children = quote! { vec![#(#children),*] };
+
+            let model = get_attribute_from_attrs(&el.attributes, "model")?;
+
+            if let Some(child) = get_attribute_from_attrs(&el.attributes, "children")? {
+                children = child;
+            }
+
+            // Element functions that need a key only accept a `Key`, the error points at the tag.
+            let key = if let Some(attr) = get_attribute_from_attrs(&el.attributes, "key")? {
+                quote! { roko_html::Key(ToString::to_string(&(#attr))) }
+            } else {
+                quote::quote_spanned! { el.name.span() => roko_html::NoKey }
+            };
+            let key =
+                quote::quote_spanned! { el.name.span() => roko_html::IntoKey::into_key(#key) };
+
+            if custom {
+                Ok(quote! {
+                    roko_html::Html::node(#name, #key, #attrs, #children)
+                })
+            } else if let Some(model) = model {
+                Ok(quote! {
+                    #tag(#model, #key, #attrs, #children)
+                })
             } else {
-                quote! { roko_html::Attribute::#name }
+                Ok(quote! {
+                    #tag(#key, #attrs, #children)
+                })
             }
         }
+        syn_rsx::Node::Attribute(attr) => transform_attribute(None, attr),
         syn_rsx::Node::Text(text) => {
             let text = text.value.as_ref().to_token_stream();
-            quote! {
-                roko_html::text(#text)
+            match syn::parse2::<syn::LitStr>(text.clone()) {
+                Ok(lit) => text::expand(&lit),
+                Err(_) => Ok(quote! { roko_html::text(#text) }),
             }
         }
         syn_rsx::Node::Block(block) => {
             let block = block.value.as_ref();
-            quote! { #block.into() }
+            Ok(quote! { #block.into() })
+        }
+        syn_rsx::Node::Fragment(fragment) => {
+            let children = transform_all(&fragment.children)?;
+            Ok(quote! { roko_html::Html::Fragment(vec![#(#children),*]) })
+        }
+        syn_rsx::Node::Comment(comment) => Err(syn::Error::new(
+            comment.value.as_ref().span(),
+            "comments are not supported in `html!`",
+        )),
+        syn_rsx::Node::Doctype(doctype) => Err(syn::Error::new(
+            doctype.value.as_ref().span(),
+            "doctypes are not supported in `html!`",
+        )),
+    }
+}
+
+/// Parses the nodes of `html!` or of a block inside of it.
+fn parse_nodes(tokens: proc_macro2::TokenStream) -> syn::Result<Vec<syn_rsx::Node>> {
+    tags::parse(bind::prepare(tokens), parser_config())
+}
+
+fn parser_config() -> ParserConfig {
+    // syn-rsx parses with syn 1, so the block is handed over to the syn 2 parsers as tokens. The
+    // cursor is walked to the end by hand because parsing a `TokenStream` leaves it in another
+    // scope, which syn-rsx rejects when it advances past the block.
+    ParserConfig::new().transform_block(|input| {
+        let tokens = input.step(|cursor| {
+            let mut rest = *cursor;
+            while let Some((_, next)) = rest.token_tree() {
+                rest = next;
+            }
+            Ok((cursor.token_stream(), rest))
+        })?;
+
+        let is_control_flow = matches!(
+            tokens.clone().into_iter().next(),
+            Some(proc_macro2::TokenTree::Ident(ident)) if ident == "if" || ident == "match" || ident == "for"
+        );
+
+        if !is_control_flow {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            control_flow
+                .parse2(tokens)
+                .unwrap_or_else(|err| err.to_compile_error()),
+        ))
+    })
+}
+
+/// Parses a braced sequence of nodes, e.g. the body of an `if`, and groups them in a fragment.
+fn nodes_in_braces(input: ParseStream) -> syn::Result<proc_macro2::TokenStream> {
+    let content;
+    braced!(content in input);
+
+    let nodes = parse_nodes(content.parse()?)?;
+    let nodes = transform_all(&nodes)?;
+
+    Ok(quote! { roko_html::Html::Fragment(vec![#(#nodes),*]) })
+}
+
+/// Parses the condition of an `if` or `if let` without its body.
+fn condition(input: ParseStream) -> syn::Result<proc_macro2::TokenStream> {
+    if input.peek(Token![let]) {
+        let let_token: Token![let] = input.parse()?;
+        let pat = Pat::parse_multi_with_leading_vert(input)?;
+        let eq: Token![=] = input.parse()?;
+        let expr = Expr::parse_without_eager_brace(input)?;
+        Ok(quote! { #let_token #pat #eq #expr })
+    } else {
+        let expr = Expr::parse_without_eager_brace(input)?;
+        Ok(quote! { #expr })
+    }
+}
+
+fn if_else(input: ParseStream) -> syn::Result<proc_macro2::TokenStream> {
+    let if_token: Token![if] = input.parse()?;
+    let cond = condition(input)?;
+    let then = nodes_in_braces(input)?;
+
+    let otherwise = if input.parse::<Option<Token![else]>>()?.is_some() {
+        if input.peek(Token![if]) {
+            if_else(input)?
+        } else {
+            nodes_in_braces(input)?
+        }
+    } else {
+        quote! { roko_html::Html::Fragment(vec![]) }
+    };
+
+    Ok(quote! { #if_token #cond { #then } else { #otherwise } })
+}
+
+fn match_arms(input: ParseStream) -> syn::Result<proc_macro2::TokenStream> {
+    let match_token: Token![match] = input.parse()?;
+    let expr = Expr::parse_without_eager_brace(input)?;
+
+    let content;
+    braced!(content in input);
+
+    let mut arms = Vec::new();
+
+    while !content.is_empty() {
+        let pat = Pat::parse_multi_with_leading_vert(&content)?;
+
+        let guard = if let Some(if_token) = content.parse::<Option<Token![if]>>()? {
+            let cond: Expr = content.parse()?;
+            quote! { #if_token #cond }
+        } else {
+            quote! {}
+        };
+
+        let arrow: Token![=>] = content.parse()?;
+        let body = nodes_in_braces(&content)?;
+        content.parse::<Option<Token![,]>>()?;
+
+        arms.push(quote! { #pat #guard #arrow #body });
+    }
+
+    Ok(quote! { #match_token #expr { #(#arms),* } })
+}
+
+fn for_loop(input: ParseStream) -> syn::Result<proc_macro2::TokenStream> {
+    let for_token: Token![for] = input.parse()?;
+    let pat = Pat::parse_multi_with_leading_vert(input)?;
+    let in_token: Token![in] = input.parse()?;
+    let expr = Expr::parse_without_eager_brace(input)?;
+    let body = nodes_in_braces(input)?;
+    let children = local("children");
+
+    Ok(quote! {
+        {
+            let mut #children = Vec::new();
+            #for_token #pat #in_token #expr {
+                #children.push(#body);
+            }
+            roko_html::Html::Fragment(#children)
         }
-        _ => todo!("Fragment is not supported"),
+    })
+}
+
+/// Blocks that start with `if`, `match` or `for` have nodes instead of Rust code in their bodies,
+/// e.g. `{for item in items { <li key={item.id}>{item.name}</li> }}`. They expand to fragments;
+/// a missing `else` renders nothing. Every other block is parsed as Rust code.
+fn control_flow(input: ParseStream) -> syn::Result<proc_macro2::TokenStream> {
+    if input.peek(Token![if]) {
+        if_else(input)
+    } else if input.peek(Token![match]) {
+        match_arms(input)
+    } else {
+        for_loop(input)
     }
 }
 
+/// Builds a view from markup, e.g. `html! { <p class="greeting">"Hello, " {name}</p> }`. Text
+/// can place variables in braces, like `"Hello, {name}!"` or `"{price:.2}"`, which are formatted
+/// with `format!`. Any other braces are kept as they are and `{{` or `}}` write a single one, so
+/// `"use {}"` is plain text. Expressions like `{user.name}` have to be a block outside of the
+/// string and placing them in text is an error.
+///
+/// An attribute without a value, like `<input disabled />`, is a boolean that is always on, and
+/// `disabled={cond}` adds or removes it:
+///
+/// ```
+/// # use roko_dom::elements::*;
+/// # use roko_macro::html;
+/// let busy = true;
+/// let form: roko_html::Html<()> = html! {
+///     <form>
+///         <input required />
+///         <button disabled={busy}>"Save"</button>
+///     </form>
+/// };
+/// ```
+///
+/// Events can't be valueless, they need the message to send:
+///
+/// ```compile_fail
+/// # use roko_dom::elements::*;
+/// # use roko_macro::html;
+/// let button: roko_html::Html<()> = html! { <button onclick>"Save"</button> };
+/// ```
+///
+/// `bind:value`, `bind:checked` and `bind:group` show a field of the model in a form element and
+/// send it back parsed into the type of the message:
+///
+/// ```
+/// # use roko_dom::elements::*;
+/// # use roko_macro::html;
+/// #[derive(Clone, PartialEq)]
+/// enum Msg {
+///     SetAge(u32),
+///     SetDone(bool),
+/// }
+///
+/// let (age, done) = (30, false);
+/// let form: roko_html::Html<Msg> = html! {
+///     <form>
+///         <input type="number" bind:value={age => Msg::SetAge} />
+///         <input type="checkbox" bind:checked={done => Msg::SetDone} />
+///     </form>
+/// };
+/// ```
+///
+/// `class:name={cond}` and `style:property={value}` are merged with `class` and `style` into one
+/// attribute, so the diff only adds or removes the classes and properties that changed:
+///
+/// ```
+/// # use roko_dom::elements::*;
+/// # use roko_macro::html;
+/// let (active, width) = (true, 40);
+/// let bar: roko_html::Html<()> = html! {
+///     <div class="bar" class:active={active} style="height: 4px" style:width={format!("{width}%")} />
+/// };
+/// ```
 #[proc_macro]
 pub fn html(item: TokenStream) -> TokenStream {
-    let html = syn_rsx::parse(item).unwrap();
-    let res = transform(&html[0]);
-    quote! {#res}.into()
+    html_nodes(item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// `html!` with the markup of a file, e.g. `include_html!("home.html")`, with a path relative to
+/// the current file like the one of `include_str!`. Its `{expr}` blocks are resolved where the
+/// macro is called, errors point at their line and column in the file, and editing the file
+/// rebuilds the crate.
+#[proc_macro]
+pub fn include_html(item: TokenStream) -> TokenStream {
+    let path = syn::parse_macro_input!(item as syn::LitStr);
+
+    template::expand(&path)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn html_nodes(item: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let html = parse_nodes(item)?;
+
+    // Multiple top-level nodes are grouped in a fragment instead of a wrapper element.
+    if let [node] = html.as_slice() {
+        transform(node)
+    } else {
+        let nodes = transform_all(&html)?;
+        Ok(quote! { roko_html::Html::Fragment(vec![#(#nodes),*]) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Expansion of `html!`, or its error.
+    fn expand(tokens: proc_macro2::TokenStream) -> Result<String, String> {
+        html_nodes(tokens)
+            .map(|tokens| tokens.to_string())
+            .map_err(|error| error.to_string())
+    }
+
+    /// Error of `html!` for the source, with the line and column where it starts.
+    fn error_at(source: &str) -> (String, usize, usize) {
+        let error = html_nodes(source.parse().unwrap()).unwrap_err();
+        let start = error.span().start();
+
+        (error.to_string(), start.line, start.column)
+    }
+
+    /// Error of `#[cmd]` for the function, with the line and column where it starts.
+    fn cmd_error_at(source: &str) -> (String, usize, usize) {
+        let error = cmd_item(syn::parse_str(source).unwrap()).unwrap_err();
+        let start = error.span().start();
+
+        (error.to_string(), start.line, start.column)
+    }
+
+    #[test]
+    fn sets_properties_of_elements() {
+        let html = expand(quote! { <input value={name} checked={done} /> }).unwrap();
+
+        assert!(html.contains("Property (\"value\""), "{html}");
+        assert!(html.contains("Property (\"checked\""), "{html}");
+    }
+
+    #[test]
+    fn passes_attributes_of_functions_as_they_are() {
+        let html = expand(quote! { <my::input value={name} checked={done} /> }).unwrap();
+
+        assert!(html.contains("Custom (\"value\""), "{html}");
+        assert!(html.contains("Custom (\"checked\""), "{html}");
+        assert!(!html.contains("Property"), "{html}");
+    }
+
+    #[test]
+    fn creates_custom_elements() {
+        let html = expand(quote! { <my-dialog open size="l" data-x="1" /> }).unwrap();
+
+        assert!(
+            html.starts_with("roko_html :: Html :: node (\"my-dialog\""),
+            "{html}"
+        );
+        assert!(html.contains("Custom (\"size\""), "{html}");
+    }
+
+    #[test]
+    fn rejects_invalid_custom_elements() {
+        assert_eq!(
+            expand(quote! { <font-face /> }),
+            Err("`<font-face>` isn't a valid custom element name, it has to be lowercase, start with a letter and not be reserved like `font-face`".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_strings_for_boolean_properties() {
+        assert_eq!(
+            expand(quote! { <option selected="no" /> }),
+            Err("`selected` is a boolean, write `selected` or `selected={cond}`".to_string())
+        );
+    }
+
+    #[test]
+    fn points_at_unbalanced_tags() {
+        assert_eq!(
+            error_at("<ul>\n  <li>\n</ul>"),
+            (
+                "expected `</li>` to close `<li>`, found `</ul>`".to_string(),
+                3,
+                2
+            )
+        );
+        assert_eq!(
+            error_at("<div>\n  <section>\n  </section>"),
+            (
+                "`<div>` is never closed, add a `</div>` or make it self-closing".to_string(),
+                1,
+                1
+            )
+        );
+    }
+
+    #[test]
+    fn points_at_empty_keys_and_models() {
+        assert_eq!(
+            error_at("<ul>\n  <li key=\"\">\"a\"</li>\n</ul>"),
+            ("`key` can't be empty".to_string(), 2, 10)
+        );
+        assert_eq!(
+            error_at("<li key>\"a\"</li>"),
+            ("`key` needs a value, e.g. `key={value}`".to_string(), 1, 4)
+        );
+        assert_eq!(
+            error_at("<div model={}></div>"),
+            ("`model` can't be empty".to_string(), 1, 11)
+        );
+    }
+
+    #[test]
+    fn points_at_invalid_commands() {
+        assert_eq!(
+            cmd_error_at("fn load(id: u32) -> Option<Msg> { None }"),
+            ("commands have to be `async` functions".to_string(), 1, 0)
+        );
+        assert_eq!(
+            cmd_error_at("async fn load(id: u32,\n    (a, b): (u8, u8)) -> Option<Msg> { None }"),
+            (
+                "arguments of commands have to be plain names, destructure them in the body"
+                    .to_string(),
+                2,
+                4
+            )
+        );
+        assert_eq!(
+            cmd_error_at("async fn load(&self) -> Option<Msg> { None }"),
+            ("commands can't take `self`".to_string(), 1, 14)
+        );
+    }
+
+    #[test]
+    fn creates_boolean_attributes() {
+        let html = expand(quote! { <button disabled={busy} hidden>"Save"</button> }).unwrap();
+
+        assert!(
+            html.contains("Attribute :: Bool (\"disabled\" . to_string () , busy)"),
+            "{html}"
+        );
+        assert!(
+            html.contains("Attribute :: Bool (\"hidden\" . to_string () , true)"),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn rejects_events_without_a_message() {
+        assert_eq!(
+            expand(quote! { <button onclick>"Save"</button> }),
+            Err("`onclick` needs the message to send, e.g. `onclick={message}`".to_string())
+        );
+    }
+
+    #[test]
+    fn passes_slots_to_components() {
+        let html = expand(quote! {
+            <Modal>
+                <slot:header><h2>"Title"</h2></slot:header>
+                <p>"Body"</p>
+                <slot:footer>"Footer"</slot:footer>
+            </Modal>
+        })
+        .unwrap();
+
+        assert!(
+            html.starts_with("Modal (ModalProps :: builder () . header (vec ! [h2"),
+            "{html}"
+        );
+        assert!(
+            html.contains(". footer (vec ! [roko_html :: text"),
+            "{html}"
+        );
+        assert!(html.contains(". children (vec ! [p"), "{html}");
+        assert!(html.ends_with(". build ())"), "{html}");
+    }
+
+    #[test]
+    fn rejects_invalid_slots() {
+        assert_eq!(
+            expand(
+                quote! { <Modal><slot:header>"a"</slot:header><slot:header>"b"</slot:header></Modal> }
+            ),
+            Err("the slot `header` is given twice".to_string())
+        );
+        assert_eq!(
+            expand(quote! { <div><slot:header>"a"</slot:header></div> }),
+            Err("slots have to be direct children of a `#[component]`".to_string())
+        );
+        assert_eq!(
+            expand(quote! { <Modal><slot:my-header>"a"</slot:my-header></Modal> }),
+            Err("slot names have to be identifiers, e.g. `<slot:header>`".to_string())
+        );
+    }
+
+    #[test]
+    fn groups_nodes_in_fragments() {
+        let fragment = expand(quote! { <>"a" <br /></> }).unwrap();
+        let nodes = expand(quote! { <p>"a"</p> <p>"b"</p> }).unwrap();
+
+        assert!(
+            fragment.starts_with("roko_html :: Html :: Fragment (vec ! [roko_html :: text"),
+            "{fragment}"
+        );
+        assert!(
+            nodes.starts_with("roko_html :: Html :: Fragment (vec ! [p"),
+            "{nodes}"
+        );
+    }
+
+    #[test]
+    fn uses_raw_names_for_keyword_tags() {
+        let html = expand(quote! { <svg><use href="#icon" /></svg> }).unwrap();
+
+        assert!(html.contains("r#use (roko_html :: IntoKey"), "{html}");
+    }
 }
@@ -0,0 +1,81 @@
+//! Code that builds an [Html] tree made while compiling, e.g. markdown rendered by
+//! `include_markdown!`. These trees come from text, so they have no handlers or context.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use roko_html::{Attribute, Html, Namespace, PropertyValue};
+
+fn namespace(namespace: Namespace) -> TokenStream {
+    match namespace {
+        Namespace::Html => quote! { roko_html::Namespace::Html },
+        Namespace::Svg => quote! { roko_html::Namespace::Svg },
+        Namespace::MathMl => quote! { roko_html::Namespace::MathMl },
+    }
+}
+
+fn attribute<Msg>(attribute: &Attribute<Msg>) -> TokenStream {
+    match attribute {
+        Attribute::Custom(name, value) => quote! {
+            roko_html::Attribute::Custom(#name.to_string(), #value.to_string())
+        },
+        Attribute::Bool(name, value) => quote! {
+            roko_html::Attribute::Bool(#name.to_string(), #value)
+        },
+        Attribute::Class(classes) => quote! {
+            roko_html::Attribute::Class(vec![#(#classes.to_string()),*])
+        },
+        Attribute::Style(declarations) => {
+            let declarations = declarations
+                .iter()
+                .map(|(name, value)| quote! { (#name.to_string(), #value.to_string()) });
+            quote! { roko_html::Attribute::Style(vec![#(#declarations),*]) }
+        }
+        Attribute::Property(name, value) => {
+            let value = match value {
+                PropertyValue::String(value) => {
+                    quote! { roko_html::PropertyValue::String(#value.to_string()) }
+                }
+                PropertyValue::Bool(value) => quote! { roko_html::PropertyValue::Bool(#value) },
+                PropertyValue::Number(value) => {
+                    quote! { roko_html::PropertyValue::Number(#value) }
+                }
+            };
+            quote! { roko_html::Attribute::Property(#name.to_string(), #value) }
+        }
+        Attribute::OnClick(_)
+        | Attribute::OnMount(_)
+        | Attribute::OnUnmount(_)
+        | Attribute::OnInput(_)
+        | Attribute::OnChange(_) => unreachable!("trees made while compiling have no handlers"),
+    }
+}
+
+pub fn html<Msg>(html: &Html<Msg>) -> TokenStream {
+    match html {
+        Html::Node(node) => {
+            let namespace = namespace(node.namespace);
+            let tag = node.tag;
+            let attributes = node.attributes.iter().map(attribute);
+            let children = node.children.iter().map(self::html);
+
+            quote! {
+                roko_html::Html::node_ns(
+                    #namespace,
+                    #tag,
+                    None,
+                    vec![#(#attributes),*],
+                    vec![#(#children),*],
+                )
+            }
+        }
+        Html::Text(text) => quote! { roko_html::text(#text) },
+        Html::UnsafeRawHtml(raw) => quote! { roko_html::unsafe_raw_html(#raw) },
+        Html::Fragment(children) => {
+            let children = children.iter().map(self::html);
+            quote! { roko_html::Html::Fragment(vec![#(#children),*]) }
+        }
+        Html::Provider(_) | Html::Consumer(_) | Html::Map(_) => {
+            unreachable!("trees made while compiling have no context or maps")
+        }
+    }
+}
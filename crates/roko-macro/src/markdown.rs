@@ -0,0 +1,271 @@
+//! Expansion of `markdown!` and `include_markdown!`. The markdown is rendered while compiling by
+//! `roko_html::markdown`, and the nodes become the code that builds them.
+//!
+//! The front-matter of a post, between `---` lines at its start, fills a struct given after the
+//! markdown, e.g. `include_markdown!("posts/intro.md", Post)` gives a `(Post, Html<Msg>)`. Its
+//! lines are `field: value`, where `true`, `false` and numbers are literals, `[a, b]` is a `Vec`
+//! and anything else is a string converted with `Into`, so quote numbers that are text.
+//!
+//! Code blocks are highlighted, and the [roko_html::highlight::THEME] that colours them is added
+//! to the stylesheet of the crate like the styles of `style!`, so pages don't have to include it.
+
+use proc_macro2::{Literal, TokenStream};
+use quote::{quote, ToTokens};
+use roko_html::highlight::THEME;
+use roko_html::{Attribute, Html};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{Ident, LitStr, Path, Token};
+
+use crate::include::{self, File};
+use crate::lower;
+use crate::style;
+
+pub struct Input {
+    source: LitStr,
+    meta: Option<Path>,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let source = input.parse()?;
+
+        let meta = if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        Ok(Self { source, meta })
+    }
+}
+
+/// Splits the front-matter from the rest of the markdown.
+fn split(source: &str) -> Option<(&str, &str)> {
+    let rest = source
+        .strip_prefix("---\n")
+        .or_else(|| source.strip_prefix("---\r\n"))?;
+
+    let mut offset = 0;
+
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+
+    None
+}
+
+fn value(text: &str) -> TokenStream {
+    let text = text.trim();
+
+    if let Some(items) = text
+        .strip_prefix('[')
+        .and_then(|text| text.strip_suffix(']'))
+    {
+        let items = items
+            .split(',')
+            .filter(|item| !item.trim().is_empty())
+            .map(value);
+        return quote! { ::std::vec![#(#items),*] };
+    }
+
+    if let Ok(boolean) = text.parse::<bool>() {
+        return boolean.to_token_stream();
+    }
+
+    if let Ok(number) = text.parse::<i64>() {
+        return Literal::i64_unsuffixed(number).to_token_stream();
+    }
+
+    if let Some(number) = text.parse::<f64>().ok().filter(|number| number.is_finite()) {
+        return Literal::f64_unsuffixed(number).to_token_stream();
+    }
+
+    let unquoted = ['"', '\'']
+        .iter()
+        .find_map(|quote| text.strip_prefix(*quote)?.strip_suffix(*quote))
+        .unwrap_or(text);
+
+    quote! { ::std::convert::Into::into(#unquoted) }
+}
+
+/// The struct literal of the front-matter, `error` gives an error at a line of the markdown.
+fn fields(
+    front_matter: &str,
+    meta: &Path,
+    error: impl Fn(usize, String) -> syn::Error,
+) -> syn::Result<TokenStream> {
+    let mut fields = Vec::new();
+
+    // The first line of the front-matter is the second one of the markdown.
+    for (number, line) in front_matter
+        .lines()
+        .enumerate()
+        .map(|(at, line)| (at + 2, line))
+    {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let Some((key, text)) = line.split_once(':') else {
+            return Err(error(number, "expected `field: value`".to_string()));
+        };
+
+        let mut field = syn::parse_str::<Ident>(key.trim())
+            .map_err(|_| error(number, format!("`{}` isn't a field name", key.trim())))?;
+        field.set_span(meta.span());
+
+        let value = value(text);
+        fields.push(quote! { #field: #value });
+    }
+
+    Ok(quote! { #meta { #(#fields),* } })
+}
+
+/// Whether the rendered markdown has highlighted code, whose classes start with `hl-`.
+fn highlighted(html: &Html<()>) -> bool {
+    match html {
+        Html::Node(node) => {
+            node.attributes.iter().any(|attribute| {
+                matches!(attribute, Attribute::Class(classes)
+                    if classes.iter().any(|class| class.starts_with("hl-")))
+            }) || node.children.iter().any(highlighted)
+        }
+        Html::Fragment(children) => children.iter().any(highlighted),
+        _ => false,
+    }
+}
+
+fn expand(input: &Input, source: &str, file: Option<&File>) -> syn::Result<TokenStream> {
+    let (front_matter, body) = match split(source) {
+        Some((front_matter, body)) => (Some(front_matter), body),
+        None => (None, source),
+    };
+
+    let html = roko_html::markdown::render::<()>(body);
+
+    if highlighted(&html) {
+        style::write("highlight".to_string(), THEME.as_bytes().to_vec()).map_err(|error| {
+            syn::Error::new(
+                input.source.span(),
+                format!("couldn't write the theme of the code: {error}"),
+            )
+        })?;
+    }
+
+    let html = lower::html(&html);
+    let track = file.map(File::track);
+
+    let error = |line: usize, message: String| {
+        let message = match file {
+            Some(_) => format!("{}:{line}: {message}", input.source.value()),
+            None => format!("line {line}: {message}"),
+        };
+        syn::Error::new(input.source.span(), message)
+    };
+
+    match (&input.meta, front_matter) {
+        (None, None) => Ok(quote! {{ #track #html }}),
+        (Some(meta), Some(front_matter)) => {
+            let meta = fields(front_matter, meta, error)?;
+            Ok(quote! {{ #track (#meta, #html) }})
+        }
+        (None, Some(_)) => Err(syn::Error::new(
+            input.source.span(),
+            "the front-matter needs a struct to go in, e.g. `include_markdown!(\"post.md\", Post)`",
+        )),
+        (Some(meta), None) => Err(syn::Error::new(
+            meta.span(),
+            format!(
+                "there's no front-matter to fill `{}` with, add it between `---` lines at the start",
+                meta.to_token_stream()
+            ),
+        )),
+    }
+}
+
+pub fn markdown(input: Input) -> syn::Result<TokenStream> {
+    expand(&input, &input.source.value(), None)
+}
+
+pub fn include_markdown(input: Input) -> syn::Result<TokenStream> {
+    let file = include::read(&input.source)?;
+    expand(&input, &file.contents, Some(&file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markdown(tokens: TokenStream) -> Result<String, String> {
+        super::markdown(syn::parse2(tokens).unwrap())
+            .map(|tokens| tokens.to_string())
+            .map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn splits_the_front_matter() {
+        assert_eq!(
+            split("---\ntitle: Hi\n---\n# Hi"),
+            Some(("title: Hi\n", "# Hi"))
+        );
+        assert_eq!(
+            split("---\r\ntitle: Hi\r\n---\r\nBody"),
+            Some(("title: Hi\r\n", "Body"))
+        );
+        assert_eq!(split("# Hi\n---\n"), None);
+        assert_eq!(split("---\ntitle: Hi\n"), None);
+    }
+
+    #[test]
+    fn converts_values() {
+        assert_eq!(value(" true").to_string(), "true");
+        assert_eq!(value("3").to_string(), "3");
+        assert_eq!(value("2.5").to_string(), "2.5");
+        assert_eq!(
+            value("'3'").to_string(),
+            ":: std :: convert :: Into :: into (\"3\")"
+        );
+        assert_eq!(
+            value("[rust, 1]").to_string(),
+            ":: std :: vec ! [:: std :: convert :: Into :: into (\"rust\") , 1]"
+        );
+    }
+
+    #[test]
+    fn fills_the_struct_with_the_front_matter() {
+        let html =
+            markdown(quote! { "---\ntitle: Hi\n# a comment\ndraft: false\n---\nBody", Post })
+                .unwrap();
+
+        assert!(
+            html.contains(
+                "(Post { title : :: std :: convert :: Into :: into (\"Hi\") , draft : false } ,"
+            ),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_front_matter() {
+        assert_eq!(
+            markdown(quote! { "---\ntitle: Hi\nnot a field\n---\n", Post }),
+            Err("line 3: expected `field: value`".to_string())
+        );
+        assert_eq!(
+            markdown(quote! { "---\nmy title: Hi\n---\n", Post }),
+            Err("line 2: `my title` isn't a field name".to_string())
+        );
+        assert_eq!(
+            markdown(quote! { "---\ntitle: Hi\n---\n" }),
+            Err("the front-matter needs a struct to go in, e.g. `include_markdown!(\"post.md\", Post)`".to_string())
+        );
+        assert_eq!(
+            markdown(quote! { "# Hi", Post }),
+            Err("there's no front-matter to fill `Post` with, add it between `---` lines at the start".to_string())
+        );
+    }
+}
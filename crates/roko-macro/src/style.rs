@@ -0,0 +1,119 @@
+//! Expansion of `style!` and `css!`, styles in SCSS scoped to a class. The styles are nested in
+//! a class named after their hash and compiled by `rsass`, and the macro gives the class name:
+//!
+//! ```
+//! # use roko_dom::elements::*;
+//! # use roko_macro::{html, style};
+//! let card = style! { padding: 20px; &:hover { color: red; } };
+//! let view: roko_html::Html<()> = html! { <div class={card}>"..."</div> };
+//!
+//! // The class only depends on the styles.
+//! assert!(card.starts_with("roko-"));
+//! assert_eq!(card, style!("padding: 20px; &:hover { color: red; }"));
+//! ```
+//!
+//! Styles that aren't valid SCSS are an error:
+//!
+//! ```compile_fail
+//! let card = roko_macro::style!("padding: 20px; &:hover { color: red; ");
+//! ```
+//!
+//! `roko-build` sets `ROKO_STYLE_DIR` while building, and the styles of each crate are written to
+//! `{crate}.css` in it, which it bundles into `static/style.css`. A crate is compiled by a single
+//! rustc that expands all of its macros, so the file is rewritten with the styles of this build
+//! and the ones that were edited or removed don't stay in it. When the last one is removed nothing
+//! rewrites it, so `roko-build` removes the files that a build didn't rewrite of the crates it
+//! compiled. Without the variable, e.g. in `cargo check`, nothing is written.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use proc_macro::{Span, TokenStream, TokenTree};
+use proc_macro2::Literal;
+use quote::quote;
+use rsass::output::{Format, Style};
+
+/// Classes and compiled styles of the crate being compiled, in the order they are expanded.
+static SHEET: Mutex<Vec<(String, Vec<u8>)>> = Mutex::new(Vec::new());
+
+/// The SCSS of the macro, either in a string or written as tokens, and the span of all of it.
+fn source(input: TokenStream) -> syn::Result<(String, Span)> {
+    let tokens = input.into_iter().collect::<Vec<_>>();
+
+    let (Some(first), Some(last)) = (tokens.first(), tokens.last()) else {
+        return Err(syn::Error::new(
+            Span::call_site().into(),
+            "expected the styles, e.g. `style! { color: red; }`",
+        ));
+    };
+
+    let span = first
+        .span()
+        .join(last.span())
+        .unwrap_or_else(|| first.span());
+
+    if let [TokenTree::Literal(literal)] = tokens.as_slice() {
+        if let Ok(literal) = syn::parse_str::<syn::LitStr>(&literal.to_string()) {
+            return Ok((literal.value(), span));
+        }
+    }
+
+    // Tokens lose the spacing of CSS, e.g. `#fff` or `-2px`, so the text is taken from the file.
+    let text = span.source_text().ok_or_else(|| {
+        syn::Error::new(
+            span.into(),
+            "the styles don't come from a file, write them in a string",
+        )
+    })?;
+
+    Ok((text, span))
+}
+
+/// Adds styles to the stylesheet of the crate and rewrites it. `name` is the class they are
+/// scoped to, or a name for global styles like the [roko_html::highlight::THEME] added by
+/// `markdown!`, and styles with a name that is already in the sheet aren't added again.
+pub fn write(name: String, css: Vec<u8>) -> std::io::Result<()> {
+    // Tracked, so a crate built outside of `roko-build` is rebuilt by it and writes its styles.
+    let Ok(dir) = proc_macro::tracked::env_var("ROKO_STYLE_DIR") else {
+        return Ok(());
+    };
+
+    let mut sheet = SHEET.lock().unwrap_or_else(|error| error.into_inner());
+
+    if sheet.iter().all(|(added, _)| *added != name) {
+        sheet.push((name, css));
+    }
+
+    let krate = std::env::var("CARGO_CRATE_NAME").unwrap_or_else(|_| "main".to_string());
+    let css = sheet
+        .iter()
+        .flat_map(|(_, css)| css)
+        .copied()
+        .collect::<Vec<_>>();
+
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(PathBuf::from(dir).join(format!("{krate}.css")), css)
+}
+
+pub fn expand(input: TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let (scss, span) = source(input)?;
+    let class = format!("roko-{:08x}", fxhash::hash32(scss.trim()));
+
+    let format = Format {
+        style: Style::Compressed,
+        ..Default::default()
+    };
+
+    let css = rsass::compile_scss(format!(".{class} {{ {scss} }}").as_bytes(), format)
+        .map_err(|error| syn::Error::new(span.into(), format!("invalid SCSS: {error}")))?;
+
+    write(class.clone(), css).map_err(|error| {
+        syn::Error::new(
+            span.into(),
+            format!("couldn't write the stylesheet of `{class}`: {error}"),
+        )
+    })?;
+
+    let class = Literal::string(&class);
+    Ok(quote! { #class })
+}
@@ -0,0 +1,211 @@
+//! Checks that the tags of an `html!` invocation are balanced. syn-rsx only reports that a close
+//! tag has no open tag, so when it fails the tokens are scanned again to point at the tag that was
+//! left open or closed with the wrong name. Blocks are skipped, their nodes are parsed on their own.
+
+use proc_macro2::{TokenStream, TokenTree};
+
+struct Tag {
+    name: String,
+    tokens: TokenStream,
+}
+
+fn is_punct(token: Option<&TokenTree>, ch: char) -> bool {
+    matches!(token, Some(TokenTree::Punct(punct)) if punct.as_char() == ch)
+}
+
+/// Reads a tag name like `div`, `home_card::Card` or `slot:header` starting at `start`.
+fn name(tokens: &[TokenTree], start: usize) -> (Tag, usize) {
+    let mut end = start;
+    let mut after_ident = false;
+
+    while let Some(token) = tokens.get(end) {
+        match token {
+            TokenTree::Ident(_) if !after_ident => after_ident = true,
+            TokenTree::Punct(punct) if matches!(punct.as_char(), ':' | '-') => after_ident = false,
+            _ => break,
+        }
+        end += 1;
+    }
+
+    let name = tokens[start..end].iter().map(ToString::to_string).collect();
+    let tokens = tokens[start..end].iter().cloned().collect();
+
+    (Tag { name, tokens }, end)
+}
+
+fn display(name: &str, close: bool) -> String {
+    format!("`<{}{name}>`", if close { "/" } else { "" })
+}
+
+pub fn check(tokens: TokenStream) -> syn::Result<()> {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut open: Vec<Tag> = Vec::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if !is_punct(tokens.get(i), '<') {
+            i += 1;
+            continue;
+        }
+
+        // Doctypes and comments, e.g. `<!-- "note" -->`, don't open anything.
+        if is_punct(tokens.get(i + 1), '!') {
+            while i < tokens.len() && !is_punct(tokens.get(i), '>') {
+                i += 1;
+            }
+            continue;
+        }
+
+        let lt = tokens[i].clone();
+        let closing = is_punct(tokens.get(i + 1), '/');
+        let (mut tag, end) = name(&tokens, i + 1 + closing as usize);
+
+        // Fragments have no name, the `<` is used to point at them.
+        if tag.tokens.is_empty() {
+            tag.tokens = lt.into();
+        }
+
+        i = end;
+
+        if closing {
+            match open.pop() {
+                Some(opened) if opened.name == tag.name => {}
+                Some(opened) => {
+                    let mut err = syn::Error::new_spanned(
+                        &tag.tokens,
+                        format!(
+                            "expected {} to close {}, found {}",
+                            display(&opened.name, true),
+                            display(&opened.name, false),
+                            display(&tag.name, true),
+                        ),
+                    );
+                    err.combine(syn::Error::new_spanned(
+                        &opened.tokens,
+                        format!("{} is opened here", display(&opened.name, false)),
+                    ));
+                    return Err(err);
+                }
+                None => {
+                    return Err(syn::Error::new_spanned(
+                        &tag.tokens,
+                        format!("{} doesn't close any tag", display(&tag.name, true)),
+                    ))
+                }
+            }
+            continue;
+        }
+
+        // Attributes go until the `>`, their values are literals or groups so they can't have one.
+        while i < tokens.len() && !is_punct(tokens.get(i), '>') {
+            i += 1;
+        }
+
+        let self_closing = is_punct(tokens.get(i - 1), '/');
+
+        if !self_closing {
+            open.push(tag);
+        }
+
+        i += 1;
+    }
+
+    match open.pop() {
+        Some(tag) => Err(syn::Error::new_spanned(
+            &tag.tokens,
+            format!(
+                "{} is never closed, add a {} or make it self-closing",
+                display(&tag.name, false),
+                display(&tag.name, true)
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Parses nodes with syn-rsx, replacing its error with a more precise one when the tags are
+/// unbalanced.
+pub fn parse(
+    tokens: TokenStream,
+    config: syn_rsx::ParserConfig,
+) -> syn::Result<Vec<syn_rsx::Node>> {
+    syn_rsx::parse2_with_config(tokens.clone(), config).map_err(|err| match check(tokens) {
+        Err(err) => err,
+        Ok(()) => syn::Error::new(err.span(), err),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Error of `check` with the line and column where it starts, or `None` when it passes.
+    fn error(source: &str) -> Option<(String, usize, usize)> {
+        let err = check(source.parse().unwrap()).err()?;
+        let start = err.span().start();
+
+        Some((err.to_string(), start.line, start.column))
+    }
+
+    #[test]
+    fn accepts_balanced_tags() {
+        assert_eq!(error(r#"<div><p>"a"</p><br /><>"b"</></div>"#), None);
+        assert_eq!(
+            error(r#"<!-- "note" --><slot:header>{x}</slot:header>"#),
+            None
+        );
+    }
+
+    #[test]
+    fn points_at_mismatched_closing_tags() {
+        assert_eq!(
+            error("<div>\n  <p>\n</div>"),
+            Some((
+                "expected `</p>` to close `<p>`, found `</div>`".to_string(),
+                3,
+                2
+            ))
+        );
+    }
+
+    #[test]
+    fn points_at_the_tag_that_is_never_closed() {
+        assert_eq!(
+            error("<div>\n  <my-card>"),
+            Some((
+                "`<my-card>` is never closed, add a `</my-card>` or make it self-closing"
+                    .to_string(),
+                2,
+                3
+            ))
+        );
+    }
+
+    #[test]
+    fn points_at_the_opening_tag_too() {
+        let err = check("<ul>\n  <li></ul>".parse().unwrap()).unwrap_err();
+        let spans = err
+            .into_iter()
+            .map(|err| (err.to_string(), err.span().start().line))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            spans,
+            [
+                (
+                    "expected `</li>` to close `<li>`, found `</ul>`".to_string(),
+                    2
+                ),
+                ("`<li>` is opened here".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn points_at_closing_tags_without_an_opening_tag() {
+        assert_eq!(
+            error("<br />\n</home::Card>"),
+            Some(("`</home::Card>` doesn't close any tag".to_string(), 2, 2))
+        );
+    }
+}
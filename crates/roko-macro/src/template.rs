@@ -0,0 +1,242 @@
+//! Expansion of `include_html!`, which reads the markup of `html!` from a file. The file is parsed
+//! into tokens, so `{expr}` blocks are resolved where the macro is called.
+//!
+//! Those tokens have the span of the call, so errors can't point into the file. When the markup
+//! has one, the token it starts at is found by parsing the markup again with some of the tokens
+//! given another span, halving them until a single one is left. Its line and column are computed
+//! from where the token is in the file.
+
+use std::ops::Range;
+
+use proc_macro2::{Delimiter, Group, Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::LitStr;
+
+use crate::include;
+
+/// Expands the markup, the error has no location when it's parsed with the compiler's tokens.
+fn html(contents: &str) -> syn::Result<TokenStream> {
+    let tokens = contents
+        .parse::<TokenStream>()
+        .map_err(|error| syn::Error::new(error.span(), error))?;
+
+    crate::html_nodes(tokens)
+}
+
+/// Gives the tokens with an index in `marked`, counted in the order they appear, the mixed site
+/// span, and the other ones the call site span.
+fn mark(tokens: TokenStream, marked: &Range<usize>, index: &mut usize) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|token| {
+            let span = if marked.contains(index) {
+                Span::mixed_site()
+            } else {
+                Span::call_site()
+            };
+            *index += 1;
+
+            match token {
+                TokenTree::Group(group) => {
+                    let mut group =
+                        Group::new(group.delimiter(), mark(group.stream(), marked, index));
+                    group.set_span(span);
+                    TokenTree::Group(group)
+                }
+                mut token => {
+                    token.set_span(span);
+                    token
+                }
+            }
+        })
+        .collect()
+}
+
+/// Whether the error of the markup starts at one of the tokens in `marked`.
+fn starts_in(tokens: &TokenStream, marked: Range<usize>) -> bool {
+    let Err(error) = crate::html_nodes(mark(tokens.clone(), &marked, &mut 0)) else {
+        return false;
+    };
+
+    // The first token of the compile error has the span where the error starts.
+    error
+        .to_compile_error()
+        .into_iter()
+        .next()
+        .is_some_and(|token| token.span().unwrap().eq(&proc_macro::Span::mixed_site()))
+}
+
+fn count(tokens: &TokenStream) -> usize {
+    tokens
+        .clone()
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Group(group) => 1 + count(&group.stream()),
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Index of the token the error of the markup starts at.
+fn failing_token(tokens: &TokenStream) -> Option<usize> {
+    let (mut start, mut end) = (0, count(tokens));
+
+    while end - start > 1 {
+        let middle = (start + end) / 2;
+
+        if starts_in(tokens, start..middle) {
+            end = middle;
+        } else {
+            start = middle;
+        }
+    }
+
+    starts_in(tokens, start..end).then_some(start)
+}
+
+/// Skips the whitespace and comments at `at`.
+fn skip(contents: &str, mut at: usize) -> usize {
+    loop {
+        let rest = &contents[at..];
+        let trimmed = rest.trim_start();
+        at += rest.len() - trimmed.len();
+
+        if trimmed.starts_with("//") {
+            at += trimmed.find('\n').unwrap_or(trimmed.len());
+        } else if trimmed.starts_with("/*") {
+            at += trimmed.find("*/").map_or(trimmed.len(), |end| end + 2);
+        } else {
+            return at;
+        }
+    }
+}
+
+/// Adds where every token is in `contents` to `out`, in the order they appear. Returns `None` if
+/// they don't match the text, e.g. because of a doc comment.
+fn offsets(
+    tokens: TokenStream,
+    contents: &str,
+    at: &mut usize,
+    out: &mut Vec<usize>,
+) -> Option<()> {
+    let expect = |at: &mut usize, text: &str| {
+        *at = skip(contents, *at);
+        contents[*at..].starts_with(text).then(|| *at += text.len())
+    };
+
+    for token in tokens {
+        out.push(skip(contents, *at));
+
+        match token {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => ("", ""),
+                };
+
+                expect(at, open)?;
+                offsets(group.stream(), contents, at, out)?;
+                expect(at, close)?;
+            }
+            token => expect(at, &token.to_string())?,
+        }
+    }
+
+    Some(())
+}
+
+/// Line and column of the token the error of the markup starts at.
+fn position(contents: &str) -> Option<(usize, usize)> {
+    let tokens = contents.parse::<TokenStream>().ok()?;
+    let token = failing_token(&tokens)?;
+
+    let mut found = Vec::new();
+    offsets(tokens, contents, &mut 0, &mut found)?;
+
+    let before = &contents[..*found.get(token)?];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count()
+        + 1;
+
+    Some((line, column))
+}
+
+/// The error of the markup with its location in the file.
+fn locate(path: &LitStr, contents: &str, error: syn::Error) -> syn::Error {
+    let message = match position(contents) {
+        Some((line, column)) => format!("{}:{line}:{column}: {error}", path.value()),
+        None => format!("{}: {error}", path.value()),
+    };
+
+    syn::Error::new(path.span(), message)
+}
+
+pub fn expand(path: &LitStr) -> syn::Result<TokenStream> {
+    let file = include::read(path)?;
+    let track = file.track();
+
+    match html(&file.contents) {
+        Ok(html) => Ok(quote! {{ #track #html }}),
+        Err(error) => Err(locate(path, &file.contents, error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text of every token at its offset in `contents`.
+    fn tokens_at(contents: &str) -> Vec<&str> {
+        let mut found = Vec::new();
+        offsets(contents.parse().unwrap(), contents, &mut 0, &mut found).unwrap();
+
+        found
+            .into_iter()
+            .map(|at| &contents[at..(at + 3).min(contents.len())])
+            .collect()
+    }
+
+    #[test]
+    fn skips_whitespace_and_comments() {
+        let contents = "  // note\n  /* block */ <p>";
+        assert_eq!(&contents[skip(contents, 0)..], "<p>");
+        assert_eq!(skip("<p>", 0), 0);
+        assert_eq!(skip("  // end", 0), 8);
+    }
+
+    #[test]
+    fn finds_the_offset_of_every_token() {
+        assert_eq!(
+            tokens_at("<p>\n  {name} // who\n</p>"),
+            ["<p>", "p>\n", ">\n ", "{na", "nam", "</p", "/p>", "p>", ">"]
+        );
+    }
+
+    #[test]
+    fn gives_up_when_tokens_differ_from_the_text() {
+        let contents = "/// doc\n<p />";
+        let mut found = Vec::new();
+
+        assert_eq!(
+            offsets(contents.parse().unwrap(), contents, &mut 0, &mut found),
+            None
+        );
+    }
+
+    #[test]
+    fn expands_the_markup() {
+        assert!(html("<p>\"Hi\"</p>").is_ok());
+        assert_eq!(
+            html("<p>\"Hi\"").unwrap_err().to_string(),
+            "`<p>` is never closed, add a `</p>` or make it self-closing"
+        );
+        assert!(html("<p>\"Hi</p>").is_err());
+    }
+}
@@ -0,0 +1,170 @@
+//! Interpolation of text nodes, e.g. `"Hello, {name}! You have {count:>3} items"`. Only a
+//! variable, with an optional format spec, is a placeholder: it's formatted with `format!`, so
+//! it's checked in the same way. Other braces are kept as they are, like in `"use {}"`, and `{{` or
+//! `}}` write a single brace. A placeholder with an expression, e.g. `{user.name}`, is an error
+//! that points at it, since it has to be a block outside of the string. The text is split into a
+//! text node for each static and dynamic segment, and the diff compares them one by one, so only
+//! the segments that changed are patched.
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{Expr, LitStr};
+
+enum Segment {
+    Static(String),
+    Dynamic(String, Span),
+}
+
+/// Whether a placeholder is a variable with an optional format spec, e.g. `name` or `price:.2`.
+fn is_variable(placeholder: &str) -> bool {
+    let name = placeholder
+        .split_once(':')
+        .filter(|(_, spec)| !spec.starts_with(':'))
+        .map_or(placeholder, |(name, _)| name);
+
+    syn::parse_str::<syn::Ident>(name).is_ok()
+}
+
+/// Whether a placeholder is an expression that can't be formatted in place, e.g. `user.name`.
+fn is_expression(placeholder: &str) -> bool {
+    matches!(
+        syn::parse_str::<Expr>(placeholder),
+        Ok(Expr::Field(_) | Expr::MethodCall(_) | Expr::Call(_) | Expr::Index(_) | Expr::Path(_))
+    )
+}
+
+/// Span of `{placeholder}` in the literal, or of the whole literal if it can't be found, e.g.
+/// because it has escapes before it.
+fn span(lit: &LitStr, placeholder: &str) -> Span {
+    let placeholder = format!("{{{placeholder}}}");
+    let token = lit.token();
+
+    token
+        .to_string()
+        .find(&placeholder)
+        .and_then(|at| token.subspan(at..at + placeholder.len()))
+        .unwrap_or_else(|| lit.span())
+}
+
+fn segments(lit: &LitStr) -> syn::Result<Vec<Segment>> {
+    let value = lit.value();
+    let mut rest = value.as_str();
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+
+    while let Some(at) = rest.find(['{', '}']) {
+        literal.push_str(&rest[..at]);
+        rest = &rest[at..];
+
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            literal.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+
+        let placeholder = rest
+            .strip_prefix('{')
+            .and_then(|inner| Some(&inner[..inner.find('}')?]));
+
+        match placeholder {
+            Some(placeholder) if is_variable(placeholder) => {
+                if !literal.is_empty() {
+                    segments.push(Segment::Static(std::mem::take(&mut literal)));
+                }
+
+                segments.push(Segment::Dynamic(
+                    placeholder.to_string(),
+                    span(lit, placeholder),
+                ));
+                rest = &rest[placeholder.len() + 2..];
+            }
+            Some(placeholder) if is_expression(placeholder) => {
+                return Err(syn::Error::new(
+                    span(lit, placeholder),
+                    format!(
+                        "only variables can be placed in text, write the expression in a block \
+                         outside of the string, e.g. `\"...\" {{{placeholder}}}`"
+                    ),
+                ));
+            }
+            _ => {
+                literal.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    literal.push_str(rest);
+
+    if !literal.is_empty() || segments.is_empty() {
+        segments.push(Segment::Static(literal));
+    }
+
+    Ok(segments)
+}
+
+/// Expands a text literal into its nodes.
+pub fn expand(lit: &LitStr) -> syn::Result<TokenStream> {
+    let nodes = segments(lit)?
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Static(text) => quote! { roko_html::text(#text) },
+            Segment::Dynamic(placeholder, span) => {
+                let format = LitStr::new(&format!("{{{placeholder}}}"), span);
+                quote! { roko_html::text(::std::format!(#format)) }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    match nodes.as_slice() {
+        [node] => Ok(node.clone()),
+        nodes => Ok(quote! { roko_html::Html::Fragment(vec![#(#nodes),*]) }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Segments of a text, with placeholders in braces.
+    fn split(text: &str) -> Result<Vec<String>, String> {
+        let segments =
+            segments(&LitStr::new(text, Span::call_site())).map_err(|e| e.to_string())?;
+
+        Ok(segments
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Static(text) => text,
+                Segment::Dynamic(placeholder, _) => format!("{{{placeholder}}}"),
+            })
+            .collect())
+    }
+
+    #[test]
+    fn interpolates_variables() {
+        assert_eq!(
+            split("Hello, {name}! {price:.2}"),
+            Ok(vec!["Hello, ", "{name}", "! ", "{price:.2}"]
+                .into_iter()
+                .map(String::from)
+                .collect())
+        );
+    }
+
+    #[test]
+    fn keeps_other_braces() {
+        assert_eq!(
+            split("{not a var} use {} {{x}} } {"),
+            Ok(vec!["{not a var} use {} {x} } {".to_string()])
+        );
+    }
+
+    #[test]
+    fn rejects_expressions() {
+        let error = split("Hi {user.name}").unwrap_err();
+        assert!(
+            error.starts_with("only variables can be placed in text"),
+            "{error}"
+        );
+    }
+}
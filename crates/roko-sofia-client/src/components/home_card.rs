@@ -1,24 +1,17 @@
-use roko_html::{to_map, Attrs, Children, Html};
-use roko_macro::html;
+use roko_html::Html;
+use roko_macro::{component, html};
 
 use roko_dom::elements::*;
 
 use crate::Message;
 
-pub fn card(_: Option<String>, attrs: Attrs<Message>, _: Children<Message>) -> Html<Message> {
-    let attrs = to_map(attrs);
-
-    let empty = "".to_string();
-
-    let title = attrs.get("title").unwrap_or(&empty);
-    let description = attrs.get("description").unwrap_or(&empty);
-    let link = attrs.get("link").unwrap_or(&empty);
-
+#[component]
+pub fn Card(title: String, description: String, link: String) -> Html<Message> {
     html! {
         <a class="home-card">
-            <h2>{title.clone()}</h2>
-            <p>{description.clone()}</p>
-            <a href={link.clone()} class="button">{"Read More"}</a>
+            <h2>{title}</h2>
+            <p>{description}</p>
+            <a href={link} class="button">{"Read More"}</a>
         </a>
     }
 }
@@ -8,7 +8,7 @@ use components::*;
 use pages::Page;
 
 use roko_dom::events::{change_hash, change_title, get_page_hash, on_hash_change};
-use roko_dom::{elements::*, Channel};
+use roko_dom::Channel;
 use roko_dom::{start, Cmd};
 use roko_html::Html;
 use roko_macro::html;
@@ -35,10 +35,8 @@ pub struct Model {
 
 fn view(model: &Model) -> Html<Message> {
     html! {
-        <div>
-            <navbar::navbar />
-            <pages::page model={model} />
-        </div>
+        <navbar::navbar />
+        <pages::page model={model} />
     }
 }
 
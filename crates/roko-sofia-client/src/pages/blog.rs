@@ -1,13 +1,58 @@
 use roko_dom::elements::*;
 use roko_html::{Attrs, Children, Html};
-use roko_macro::html;
+use roko_macro::{html, include_markdown, style};
 
 use crate::{Message, Model};
 
+/// Front-matter of a post.
+pub struct Post {
+    pub title: &'static str,
+    pub date: &'static str,
+}
+
+/// Posts from the newest to the oldest.
+fn posts() -> Vec<(Post, Html<Message>)> {
+    vec![
+        include_markdown!("../../posts/writing-a-virtual-dom.md", Post),
+        include_markdown!("../../posts/hello-world.md", Post),
+    ]
+}
+
 pub fn page(_model: &Model, _attrs: Attrs<Message>, _children: Children<Message>) -> Html<Message> {
+    let blog = style! {
+        width: 60%;
+        margin: auto;
+        padding: 40px 0;
+        color: #fff;
+
+        .post {
+            margin-bottom: 60px;
+            line-height: 1.6;
+
+            time {
+                color: #918f90;
+                font-size: 13px;
+            }
+
+            pre {
+                padding: 20px;
+                overflow-x: auto;
+                background: rgb(45, 45, 49);
+                border-radius: 10px;
+                border: 2px solid #3d3d42;
+            }
+        }
+    };
+
     html! {
-        <div>
-            "blog"
-        </div>
+        <main class={blog}>
+            {for (post, body) in posts() {
+                <article class="post">
+                    <h1>{post.title}</h1>
+                    <time>{post.date}</time>
+                    {body}
+                </article>
+            }}
+        </main>
     }
 }